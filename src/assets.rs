@@ -0,0 +1,276 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use serde::Deserialize;
+
+use crate::{config, gpu::GpuAtlas, settings::settings};
+
+const ASSETS_DIR: &str = "assets";
+const MANIFEST_FILE: &str = "atlases.toml";
+
+/// Size of a tile in the built-in atlases.
+const BUILTIN_TILE_SIZE: usize = 32;
+
+/// How an atlas image is laid out, as read from `atlases.toml` in an asset
+/// pack.
+#[derive(Debug, Clone, Deserialize)]
+struct AtlasManifest {
+    /// Path to a PNG, relative to the asset pack.
+    image: PathBuf,
+    /// Width and height of a single tile, in pixels.
+    tile_size: usize,
+    /// Number of tiles in each row.
+    columns: usize,
+    /// Characters to draw with a different tile than the built-in font uses,
+    /// as `column, row`. Only used for the font.
+    #[serde(default)]
+    glyphs: BTreeMap<char, [usize; 2]>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    box_border: Option<AtlasManifest>,
+    font: Option<AtlasManifest>,
+    pokemon_icons: Option<AtlasManifest>,
+}
+
+pub struct Atlas {
+    /// RGBA pixels.
+    pixels: Vec<u8>,
+    width: usize,
+    height: usize,
+    tile_size: usize,
+    glyphs: BTreeMap<char, [usize; 2]>,
+}
+
+impl Atlas {
+    fn from_png(bytes: &[u8], tile_size: usize, columns: usize) -> io::Result<Atlas> {
+        let image = image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .into_rgba8();
+
+        let (width, height) = (image.width() as usize, image.height() as usize);
+
+        if tile_size == 0 || width != tile_size * columns || height % tile_size != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Image is {}×{}, expected {} columns of {} pixel tiles",
+                    width, height, columns, tile_size
+                ),
+            ));
+        }
+
+        Ok(Atlas {
+            pixels: image.into_raw(),
+            width,
+            height,
+            tile_size,
+            glyphs: BTreeMap::new(),
+        })
+    }
+
+    fn builtin(bytes: &[u8], columns: usize) -> Atlas {
+        Atlas::from_png(bytes, BUILTIN_TILE_SIZE, columns).unwrap()
+    }
+
+    fn load(dir: &Path, manifest: &AtlasManifest) -> io::Result<Atlas> {
+        let bytes = fs::read(dir.join(&manifest.image))?;
+        let mut atlas = Atlas::from_png(&bytes, manifest.tile_size, manifest.columns)?;
+        atlas.glyphs = manifest.glyphs.clone();
+
+        Ok(atlas)
+    }
+
+    /// The RGBA colour at `x`, `y`, with the atlas resampled so that its
+    /// tiles are `tile_size` pixels.
+    pub fn pixel(&self, x: usize, y: usize, tile_size: usize) -> &[u8] {
+        let x = (x * self.tile_size / tile_size).min(self.width - 1);
+        let y = (y * self.tile_size / tile_size).min(self.height - 1);
+        let idx = (y * self.width + x) * 4;
+
+        &self.pixels[idx..idx + 4]
+    }
+
+    /// Whether the `w` by `h` region at `x`, `y` is inside the atlas, with the
+    /// atlas resampled so that its tiles are `tile_size` pixels.
+    pub fn contains(&self, x: usize, y: usize, w: usize, h: usize, tile_size: usize) -> bool {
+        (x + w) * self.tile_size <= self.width * tile_size
+            && (y + h) * self.tile_size <= self.height * tile_size
+    }
+
+    /// The tile to draw `chr` with, if the asset pack overrides it.
+    pub fn glyph(&self, chr: char) -> Option<(usize, usize)> {
+        self.glyphs.get(&chr).map(|&[x, y]| (x, y))
+    }
+}
+
+pub struct Atlases {
+    box_border: Atlas,
+    font: Atlas,
+    pokemon_icons: Atlas,
+}
+
+impl Atlases {
+    fn builtin() -> Atlases {
+        Atlases {
+            box_border: Atlas::builtin(include_bytes!("../gfx/box_border.png"), 3),
+            font: Atlas::builtin(include_bytes!("../gfx/font.png"), 16),
+            pokemon_icons: Atlas::builtin(include_bytes!("../gfx/pokemon_icons.png"), 32),
+        }
+    }
+
+    /// The built-in atlases, with those described in the manifest in `dir`
+    /// replaced. Atlases that fail to load are logged and left as built-in.
+    fn load(dir: &Path) -> Atlases {
+        let mut result = Atlases::builtin();
+
+        let manifest = match read_manifest(dir) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                log::error!(
+                    "Error reading asset pack manifest in {}: {}",
+                    dir.display(),
+                    e
+                );
+                return result;
+            }
+        };
+
+        let replacements = [
+            (&mut result.box_border, manifest.box_border),
+            (&mut result.font, manifest.font),
+            (&mut result.pokemon_icons, manifest.pokemon_icons),
+        ];
+
+        for (atlas, manifest) in replacements {
+            if let Some(manifest) = manifest {
+                match Atlas::load(dir, &manifest) {
+                    Ok(loaded) => *atlas = loaded,
+                    Err(e) => log::error!("Error loading {}: {}", manifest.image.display(), e),
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn get(&self, atlas: GpuAtlas) -> &Atlas {
+        match atlas {
+            GpuAtlas::BoxBorder => &self.box_border,
+            GpuAtlas::Font => &self.font,
+            GpuAtlas::PokemonIcons => &self.pokemon_icons,
+        }
+    }
+}
+
+fn read_manifest(dir: &Path) -> io::Result<Manifest> {
+    let text = match fs::read_to_string(dir.join(MANIFEST_FILE)) {
+        Ok(text) => text,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Manifest::default()),
+        Err(e) => return Err(e),
+    };
+
+    toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// The asset pack from the settings, or `assets` in the config directory if
+/// there is one.
+fn get_asset_pack_dir() -> Option<PathBuf> {
+    if let Some(dir) = &settings().asset_pack {
+        return Some(dir.clone());
+    }
+
+    let dir = config::get_config_path(ASSETS_DIR).ok()?;
+    dir.is_dir().then_some(dir)
+}
+
+static ATLASES: OnceLock<Atlases> = OnceLock::new();
+
+/// The UI atlases, loaded from the asset pack the first time this is called.
+pub fn atlases() -> &'static Atlases {
+    ATLASES.get_or_init(|| match get_asset_pack_dir() {
+        Some(dir) => Atlases::load(&dir),
+        None => Atlases::builtin(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// A PNG where each pixel's red and green are its x and y.
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([x as u8, y as u8, 0, 255])
+        });
+
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_from_png() {
+        assert!(Atlas::from_png(&png(16, 24), 8, 2).is_ok());
+
+        assert!(Atlas::from_png(&png(20, 24), 8, 2).is_err());
+        assert!(Atlas::from_png(&png(16, 20), 8, 2).is_err());
+        assert!(Atlas::from_png(&png(16, 24), 0, 2).is_err());
+        assert!(Atlas::from_png(b"not a png", 8, 2).is_err());
+    }
+
+    #[test]
+    fn test_resample() {
+        // Two columns of 2 pixel tiles, one row
+        let atlas = Atlas::from_png(&png(4, 2), 2, 2).unwrap();
+
+        // Drawn with 4 pixel tiles, each pixel covers two
+        assert_eq!(atlas.pixel(0, 0, 4), [0, 0, 0, 255]);
+        assert_eq!(atlas.pixel(3, 1, 4), [1, 0, 0, 255]);
+        assert_eq!(atlas.pixel(7, 3, 4), [3, 1, 0, 255]);
+
+        // Drawn with 1 pixel tiles, every other pixel is skipped
+        assert_eq!(atlas.pixel(1, 0, 1), [2, 0, 0, 255]);
+
+        assert!(atlas.contains(0, 0, 8, 4, 4));
+        assert!(atlas.contains(4, 0, 4, 4, 4));
+        assert!(!atlas.contains(0, 0, 9, 4, 4));
+        assert!(!atlas.contains(0, 1, 8, 4, 4));
+        assert!(atlas.contains(1, 0, 1, 1, 1));
+        assert!(!atlas.contains(2, 0, 1, 1, 1));
+    }
+
+    #[test]
+    fn test_read_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let manifest = read_manifest(dir.path()).unwrap();
+        assert!(manifest.font.is_none());
+        assert!(manifest.box_border.is_none());
+        assert!(manifest.pokemon_icons.is_none());
+
+        fs::write(
+            dir.path().join(MANIFEST_FILE),
+            "[font]\nimage = \"font.png\"\ntile_size = 8\ncolumns = 16\nglyphs = { \"&\" = [9, 6] }\n",
+        )
+        .unwrap();
+
+        let font = read_manifest(dir.path()).unwrap().font.unwrap();
+        assert_eq!(font.image, PathBuf::from("font.png"));
+        assert_eq!((font.tile_size, font.columns), (8, 16));
+        assert_eq!(font.glyphs.get(&'&'), Some(&[9, 6]));
+
+        fs::write(dir.path().join(MANIFEST_FILE), "[font\nimage = ").unwrap();
+        let err = read_manifest(dir.path()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
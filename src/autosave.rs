@@ -0,0 +1,265 @@
+use crate::{
+    cpu::Cpu,
+    game::{constants::tileset_constants, engine::menus::save, ram::wram},
+    hud::show_toast,
+    saves,
+    settings::{settings, AutosaveSettings},
+};
+
+/// How many frames nothing has to change for before autosaving, so that a
+/// pending autosave doesn't happen halfway through a warp or a battle
+/// transition.
+const SETTLE_FRAMES: u32 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutosaveTrigger {
+    Interval,
+    PokemonCenter,
+    Catch,
+}
+
+/// The parts of the game state the triggers look at, read once per frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    pub map: u8,
+    pub tileset: u8,
+    pub in_battle: u8,
+    /// Pokémon in the party and the current box.
+    pub mons: usize,
+    pub play_time_minutes: u32,
+    /// Whether saving is possible this frame, see [`save::is_overworld_idle`].
+    pub can_save: bool,
+}
+
+impl Snapshot {
+    fn read(cpu: &mut Cpu, can_save: bool) -> Snapshot {
+        let hours = cpu.read_byte(wram::W_PLAY_TIME_HOURS) as u32;
+        let minutes = cpu.read_byte(wram::W_PLAY_TIME_MINUTES) as u32;
+        let mons = cpu.borrow_wram().party().len() + cpu.borrow_wram().r#box().len();
+
+        Snapshot {
+            map: cpu.read_byte(wram::W_CUR_MAP),
+            tileset: cpu.read_byte(wram::W_CUR_MAP_TILESET) & 0x7f,
+            in_battle: cpu.read_byte(wram::W_IS_IN_BATTLE),
+            mons,
+            play_time_minutes: hours * 60 + minutes,
+            can_save,
+        }
+    }
+}
+
+/// Watches the game for the moments to autosave at. This starts over whenever
+/// a different save is loaded.
+#[derive(Debug, Default)]
+pub struct AutosaveTriggers {
+    last: Option<Snapshot>,
+    settled_frames: u32,
+    last_autosave_minutes: u32,
+    mons_at_battle_start: Option<usize>,
+    pending: Option<AutosaveTrigger>,
+}
+
+impl AutosaveTriggers {
+    /// Look at the game state for this frame, returning why to autosave now,
+    /// if at all.
+    pub fn update(
+        &mut self,
+        now: Snapshot,
+        settings: &AutosaveSettings,
+    ) -> Option<AutosaveTrigger> {
+        let prev = match self.last.replace(now) {
+            Some(prev) => prev,
+            None => {
+                self.last_autosave_minutes = now.play_time_minutes;
+                return None;
+            }
+        };
+
+        if (now.map, now.tileset, now.in_battle) == (prev.map, prev.tileset, prev.in_battle) {
+            self.settled_frames = self.settled_frames.saturating_add(1);
+        } else {
+            self.settled_frames = 0;
+        }
+
+        if settings.on_pokemon_center
+            && now.tileset == tileset_constants::POKECENTER
+            && prev.tileset != tileset_constants::POKECENTER
+        {
+            self.pending.get_or_insert(AutosaveTrigger::PokemonCenter);
+        }
+
+        if prev.in_battle == 0 && now.in_battle != 0 {
+            self.mons_at_battle_start = Some(now.mons);
+        }
+
+        if prev.in_battle != 0 && now.in_battle == 0 {
+            let caught = self
+                .mons_at_battle_start
+                .take()
+                .is_some_and(|mons| now.mons > mons);
+
+            if settings.on_catch && caught {
+                self.pending.get_or_insert(AutosaveTrigger::Catch);
+            }
+        }
+
+        if settings.interval_minutes > 0
+            && now.play_time_minutes
+                >= self.last_autosave_minutes + settings.interval_minutes as u32
+        {
+            self.pending.get_or_insert(AutosaveTrigger::Interval);
+        }
+
+        if now.in_battle != 0 || !now.can_save || self.settled_frames < SETTLE_FRAMES {
+            return None;
+        }
+
+        let trigger = self.pending.take()?;
+        self.last_autosave_minutes = now.play_time_minutes;
+        Some(trigger)
+    }
+}
+
+/// Autosave if one of the enabled triggers has happened. This runs once per
+/// frame, at the start of the VBlank interrupt, and only saves when
+/// `can_save` is set.
+pub(crate) fn check_autosave(cpu: &mut Cpu, can_save: bool) {
+    let settings = settings().autosave.clone();
+
+    if !settings.enabled || cpu.save_path().is_none() {
+        return;
+    }
+
+    let now = Snapshot::read(cpu, can_save);
+
+    if let Some(trigger) = cpu.autosave_triggers_mut().update(now, &settings) {
+        log::info!("Autosaving: {:?}", trigger);
+        autosave(cpu, settings.slots);
+    }
+}
+
+/// Write the current progress to the autosave slots, using the game's own
+/// `SaveSAVtoSRAM` like saving from the START menu, without touching the save
+/// itself.
+fn autosave(cpu: &mut Cpu, slots: u8) {
+    let save_path = match cpu.save_path() {
+        Some(path) => path.to_owned(),
+        None => return,
+    };
+
+    let manual_save = cpu.borrow_sram().clone();
+    save::run_save_sav_to_sram(cpu);
+
+    let mut data = cpu.borrow_sram().clone();
+    data.update_checksums();
+    cpu.replace_ram(manual_save);
+
+    match saves::write_autosave(&save_path, &data, slots) {
+        Ok(()) => show_toast("Autosaved"),
+        Err(e) => {
+            log::error!("Error autosaving {}: {}", save_path.display(), e);
+            show_toast("Error autosaving");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            map: 0,
+            tileset: 0,
+            in_battle: 0,
+            mons: 1,
+            play_time_minutes: 0,
+            can_save: true,
+        }
+    }
+
+    /// Feed `now` to the triggers for long enough to settle.
+    fn settle(triggers: &mut AutosaveTriggers, now: Snapshot) -> Option<AutosaveTrigger> {
+        let settings = AutosaveSettings::default();
+        (0..=SETTLE_FRAMES).find_map(|_| triggers.update(now, &settings))
+    }
+
+    #[test]
+    fn test_pokemon_center_trigger() {
+        let mut triggers = AutosaveTriggers::default();
+        assert_eq!(settle(&mut triggers, snapshot()), None);
+
+        let center = Snapshot {
+            map: 0x29,
+            tileset: tileset_constants::POKECENTER,
+            ..snapshot()
+        };
+        assert_eq!(
+            settle(&mut triggers, center),
+            Some(AutosaveTrigger::PokemonCenter)
+        );
+        assert_eq!(settle(&mut triggers, center), None);
+    }
+
+    #[test]
+    fn test_catch_waits_for_battle_to_end() {
+        let mut triggers = AutosaveTriggers::default();
+        settle(&mut triggers, snapshot());
+
+        let battle = Snapshot {
+            in_battle: 1,
+            ..snapshot()
+        };
+        assert_eq!(settle(&mut triggers, battle), None);
+        assert_eq!(settle(&mut triggers, Snapshot { mons: 2, ..battle }), None);
+
+        let after = Snapshot {
+            mons: 2,
+            ..snapshot()
+        };
+        assert_eq!(settle(&mut triggers, after), Some(AutosaveTrigger::Catch));
+    }
+
+    #[test]
+    fn test_waits_until_saving_is_possible() {
+        let mut triggers = AutosaveTriggers::default();
+        settle(&mut triggers, snapshot());
+
+        let center = Snapshot {
+            tileset: tileset_constants::POKECENTER,
+            can_save: false,
+            ..snapshot()
+        };
+        assert_eq!(settle(&mut triggers, center), None);
+
+        let center = Snapshot {
+            can_save: true,
+            ..center
+        };
+        assert_eq!(
+            settle(&mut triggers, center),
+            Some(AutosaveTrigger::PokemonCenter)
+        );
+    }
+
+    #[test]
+    fn test_interval_trigger() {
+        let mut triggers = AutosaveTriggers::default();
+        settle(&mut triggers, snapshot());
+
+        let later = Snapshot {
+            play_time_minutes: 14,
+            ..snapshot()
+        };
+        assert_eq!(settle(&mut triggers, later), None);
+
+        let later = Snapshot {
+            play_time_minutes: 15,
+            ..snapshot()
+        };
+        assert_eq!(
+            settle(&mut triggers, later),
+            Some(AutosaveTrigger::Interval)
+        );
+    }
+}
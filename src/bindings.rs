@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::keypad::{KeyboardKey, KeypadKey};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    A,
+    B,
+    Start,
+    Select,
+    Speed1,
+    Speed2,
+    Speed3,
+    Speed4,
+    Speed5,
+    Speed6,
+    FastForward,
+    Turbo,
+    Screenshot,
+    /// Save the game to its save file, the same as from the START menu.
+    #[serde(alias = "save_state")]
+    QuickSave,
+    Mute,
+    Palette,
+    ShowFps,
+    Pause,
+}
+
+impl Action {
+    pub const JOYPAD: [Action; 8] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::A,
+        Action::B,
+        Action::Start,
+        Action::Select,
+    ];
+
+    pub const SPEED: [Action; 8] = [
+        Action::Speed1,
+        Action::Speed2,
+        Action::Speed3,
+        Action::Speed4,
+        Action::Speed5,
+        Action::Speed6,
+        Action::FastForward,
+        Action::Turbo,
+    ];
+
+    pub const OTHER: [Action; 6] = [
+        Action::Screenshot,
+        Action::QuickSave,
+        Action::Mute,
+        Action::Palette,
+        Action::ShowFps,
+        Action::Pause,
+    ];
+
+    pub fn all() -> impl Iterator<Item = Action> {
+        Action::JOYPAD
+            .into_iter()
+            .chain(Action::SPEED)
+            .chain(Action::OTHER)
+    }
+
+    pub fn keypad_key(self) -> Option<KeypadKey> {
+        match self {
+            Action::Up => Some(KeypadKey::Up),
+            Action::Down => Some(KeypadKey::Down),
+            Action::Left => Some(KeypadKey::Left),
+            Action::Right => Some(KeypadKey::Right),
+            Action::A => Some(KeypadKey::A),
+            Action::B => Some(KeypadKey::B),
+            Action::Start => Some(KeypadKey::Start),
+            Action::Select => Some(KeypadKey::Select),
+            _ => None,
+        }
+    }
+
+    /// Emulation speed preset, where 1 is the original speed.
+    pub fn speed(self) -> Option<u8> {
+        match self {
+            Action::Speed1 => Some(1),
+            Action::Speed2 => Some(2),
+            Action::Speed3 => Some(3),
+            Action::Speed4 => Some(4),
+            Action::Speed5 => Some(5),
+            Action::Speed6 => Some(6),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Up => "UP",
+            Action::Down => "DOWN",
+            Action::Left => "LEFT",
+            Action::Right => "RIGHT",
+            Action::A => "A",
+            Action::B => "B",
+            Action::Start => "START",
+            Action::Select => "SELECT",
+            Action::Speed1 => "SPEED 1",
+            Action::Speed2 => "SPEED 2",
+            Action::Speed3 => "SPEED 3",
+            Action::Speed4 => "SPEED 4",
+            Action::Speed5 => "SPEED 5",
+            Action::Speed6 => "SPEED 6",
+            Action::FastForward => "FAST FWD",
+            Action::Turbo => "TURBO",
+            Action::Screenshot => "SCREENSHOT",
+            Action::QuickSave => "QUICK SAVE",
+            Action::Mute => "MUTE",
+            Action::Palette => "PALETTE",
+            Action::ShowFps => "SHOW FPS",
+            Action::Pause => "PAUSE",
+        }
+    }
+
+    fn default_keys(self) -> &'static [KeyboardKey] {
+        match self {
+            Action::Up => &[KeyboardKey::Up, KeyboardKey::W],
+            Action::Down => &[KeyboardKey::Down, KeyboardKey::S],
+            Action::Left => &[KeyboardKey::Left, KeyboardKey::A],
+            Action::Right => &[KeyboardKey::Right, KeyboardKey::D],
+            Action::A => &[KeyboardKey::Z, KeyboardKey::N],
+            Action::B => &[KeyboardKey::X, KeyboardKey::M],
+            Action::Start => &[KeyboardKey::Return],
+            Action::Select => &[KeyboardKey::Space],
+            Action::Speed1 => &[KeyboardKey::Key1],
+            Action::Speed2 => &[KeyboardKey::Key2],
+            Action::Speed3 => &[KeyboardKey::Key3],
+            Action::Speed4 => &[KeyboardKey::Key4],
+            Action::Speed5 => &[KeyboardKey::Key5],
+            Action::Speed6 => &[KeyboardKey::Key6],
+            Action::FastForward => &[KeyboardKey::Tab],
+            Action::Turbo => &[KeyboardKey::Grave],
+            Action::Screenshot => &[KeyboardKey::F12],
+            Action::QuickSave => &[KeyboardKey::F5],
+            Action::Mute => &[KeyboardKey::F9],
+            Action::Palette => &[KeyboardKey::F8],
+            Action::ShowFps => &[KeyboardKey::F3],
+            Action::Pause => &[KeyboardKey::P],
+        }
+    }
+}
+
+/// Maps every action to the keys that trigger it. A key triggers at most one
+/// action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KeyBindings {
+    keys: BTreeMap<Action, Vec<KeyboardKey>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            keys: Action::all()
+                .map(|action| (action, action.default_keys().to_vec()))
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Bind the default keys for any action that has no entry, unless those
+    /// keys are already used by another action.
+    pub fn fill_missing_defaults(&mut self) {
+        for action in Action::all() {
+            if !self.keys.contains_key(&action) {
+                let keys = action
+                    .default_keys()
+                    .iter()
+                    .copied()
+                    .filter(|&key| self.action_for(key).is_none())
+                    .collect();
+
+                self.keys.insert(action, keys);
+            }
+        }
+    }
+
+    pub fn action_for(&self, key: KeyboardKey) -> Option<Action> {
+        self.keys
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(&action, _)| action)
+    }
+
+    pub fn keys_for(&self, action: Action) -> &[KeyboardKey] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Make `key` the only key that triggers `action`, removing it from any
+    /// other action it was bound to.
+    pub fn rebind(&mut self, action: Action, key: KeyboardKey) {
+        for keys in self.keys.values_mut() {
+            keys.retain(|&k| k != key);
+        }
+
+        self.keys.insert(action, vec![key]);
+    }
+
+    /// Add `key` as an additional key for `action`, removing it from any other
+    /// action it was bound to.
+    pub fn bind(&mut self, action: Action, key: KeyboardKey) {
+        for keys in self.keys.values_mut() {
+            keys.retain(|&k| k != key);
+        }
+
+        self.keys.entry(action).or_default().push(key);
+    }
+}
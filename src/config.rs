@@ -0,0 +1,82 @@
+// Configuration lives next to the saves on macOS and Windows, and follows the
+// XDG Base Directory specification on Linux. In portable mode, both live next
+// to the executable instead.
+
+use std::{
+    env, fs,
+    io::{Error, ErrorKind, Result},
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// A file next to the executable that turns on portable mode.
+const PORTABLE_MARKER: &str = "portable.txt";
+
+static PORTABLE: AtomicBool = AtomicBool::new(false);
+
+/// Turn on portable mode for this session, e.g. from the command line.
+pub fn set_portable(portable: bool) {
+    PORTABLE.store(portable, Ordering::Relaxed);
+}
+
+/// Directory of the executable, where everything is kept in portable mode.
+fn get_portable_dir() -> Result<PathBuf> {
+    let exe = env::current_exe()?;
+
+    exe.parent()
+        .map(|dir| dir.to_owned())
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "Executable has no parent directory"))
+}
+
+/// The portable directory, if portable mode is on either for this session or
+/// by a `portable.txt` next to the executable.
+pub fn portable_dir() -> Option<PathBuf> {
+    let dir = get_portable_dir().ok()?;
+
+    (PORTABLE.load(Ordering::Relaxed) || dir.join(PORTABLE_MARKER).exists()).then_some(dir)
+}
+
+/// An environment variable holding a directory, or an error explaining that
+/// it's missing.
+pub fn env_dir(name: &str) -> Result<PathBuf> {
+    match env::var_os(name) {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("{} is not set, can't find where to keep files", name),
+        )),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn get_platform_config_dir() -> Result<PathBuf> {
+    Ok(env_dir("HOME")?.join("Library/Application Support/Rustic Yellow"))
+}
+
+#[cfg(target_os = "linux")]
+fn get_platform_config_dir() -> Result<PathBuf> {
+    match env_dir("XDG_CONFIG_HOME") {
+        Ok(dir) => Ok(dir.join("rustic-yellow")),
+        Err(_) => Ok(env_dir("HOME")?.join(".config/rustic-yellow")),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn get_platform_config_dir() -> Result<PathBuf> {
+    Ok(env_dir("appdata")?.join("Rustic Yellow"))
+}
+
+fn get_config_dir() -> Result<PathBuf> {
+    match portable_dir() {
+        Some(dir) => Ok(dir),
+        None => get_platform_config_dir(),
+    }
+}
+
+pub fn create_config_dir() -> Result<()> {
+    fs::create_dir_all(get_config_dir()?)
+}
+
+pub fn get_config_path(name: &str) -> Result<PathBuf> {
+    Ok(get_config_dir()?.join(name))
+}
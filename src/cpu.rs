@@ -1,19 +1,29 @@
 use std::{
-    path,
+    collections::VecDeque,
+    io, path,
     sync::mpsc::{Receiver, SyncSender},
 };
 
 use crate::{
+    autosave::{self, AutosaveTriggers},
+    bindings::Action,
+    game::{self, engine::menus::save, ExitStatus},
     game_state::GameState,
     gpu::GpuLayer,
-    keypad::{KeyboardEvent, KeypadKey, TextEvent},
+    hud::show_toast,
+    keypad::{KeyboardEvent, KeyboardKey, KeypadKey, QuitRequested, TextEvent},
     mmu::Mmu,
+    palette::Palette,
+    save_metadata,
     save_state::SaveState,
+    settings::{settings, settings_mut},
     sound2::{Music, Sfx},
-    PokemonSpecies,
 };
 use CpuFlag::{C, H, N, Z};
 
+/// How many hook invocations to keep for crash reports.
+const RECENT_HOOKS: usize = 32;
+
 #[derive(Copy, Clone)]
 pub enum CpuFlag {
     C = 0b00010000,
@@ -40,15 +50,20 @@ pub struct Cpu {
     pub(crate) setei: u32,
 
     pub(crate) mmu: Mmu,
-
-    pub(crate) starter: PokemonSpecies,
+    /// The player asked to quit, and will be offered to save once possible.
+    quit_pending: bool,
+    /// The offer to save before quitting is showing.
+    quitting: bool,
+    /// Bank and address of the most recent calls into Rust, for crash reports.
+    recent_hooks: VecDeque<(usize, u16)>,
+
+    autosave_triggers: AutosaveTriggers,
 }
 
 impl Cpu {
     pub fn new(
         update_screen: SyncSender<Vec<u8>>,
         keyboard_events: Receiver<KeyboardEvent>,
-        starter: PokemonSpecies,
     ) -> Cpu {
         Cpu {
             a: 0x11,
@@ -68,8 +83,10 @@ impl Cpu {
             setei: 0,
 
             mmu: Mmu::new(update_screen, keyboard_events),
-
-            starter,
+            quit_pending: false,
+            quitting: false,
+            recent_hooks: VecDeque::with_capacity(RECENT_HOOKS),
+            autosave_triggers: AutosaveTriggers::default(),
         }
     }
 
@@ -85,32 +102,45 @@ impl Cpu {
         self.pc = pc;
 
         loop {
-            match (self.bank(), self.pc) {
+            let hook: fn(&mut Cpu) = match (self.bank(), self.pc) {
                 (_, 0x0000) => break,
                 (_, 0x0001) => panic!("Invalid call to 0x0001"),
-                (_, 0x143e) => crate::game::home::pics::load_mon_front_sprite(self),
-                (_, 0x2238) => crate::game::home::audio::play_sound(self),
-                (0x01, 0x42bf) => crate::game::engine::movie::title::display_title_screen_go_to_main_menu(self),
+                (_, 0x143e) => crate::game::home::pics::load_mon_front_sprite,
+                (_, 0x2238) => crate::game::home::audio::play_sound,
+                (0x01, 0x42bf) => crate::game::engine::movie::title::display_title_screen_go_to_main_menu,
                 (0x01, 0x5ba6) => panic!("main_menu should only be called from Rust"),
                 (0x01, 0x5dfb) => panic!("check_for_player_name_in_sram should only be called from Rust"),
-                (0x03, 0x6807) => crate::game::engine::items::item_effects::hook_send_new_mon_to_box_end(self),
-                (0x06, 0x4f0a) => crate::game::scripts::pallete_town::pallet_town_script4(self),
-                (0x07, 0x4b40) => crate::game::scripts::oaks_lab::oaks_lab_text18(self),
-                (0x08, 0x5495) => crate::game::engine::pokemon::bills_pc::bills_pc_menu(self),
-                (0x1c, 0x61f8) => crate::game::engine::gfx::palettes::load_sgb(self),
-                (0x1c, 0x7b91) => crate::game::engine::menus::save::save_sav_to_sram(self),
-                (0x3c, 0x4000) => crate::game::engine::pikachu::pikachu_pcm::play_pikachu_sound_clip(self),
-                (0x3d, 0x6178) => crate::game::engine::battle::init_battle::load_mon_back_pic(self),
-                (0x3d, 0x674d) => crate::game::engine::events::give_pokemon::hook_give_pokemon_next_end(self),
+                (0x03, 0x6807) => crate::game::engine::items::item_effects::hook_send_new_mon_to_box_end,
+                (0x06, 0x4f0a) => crate::game::scripts::pallete_town::pallet_town_script4,
+                (0x07, 0x4b40) => crate::game::scripts::oaks_lab::oaks_lab_text18,
+                (0x08, 0x5495) => crate::game::engine::pokemon::bills_pc::bills_pc_menu,
+                (0x1c, 0x61f8) => crate::game::engine::gfx::palettes::load_sgb,
+                (0x1c, 0x7b91) => crate::game::engine::menus::save::save_sav_to_sram,
+                (0x3c, 0x4000) => crate::game::engine::pikachu::pikachu_pcm::play_pikachu_sound_clip,
+                (0x3d, 0x6178) => crate::game::engine::battle::init_battle::load_mon_back_pic,
+                (0x3d, 0x674d) => crate::game::engine::events::give_pokemon::hook_give_pokemon_next_end,
 
                 _ => {
                     let ticks = if self.halted { 4 } else { self.step() * 4 };
                     self.cycle(ticks);
+                    continue;
                 }
+            };
+
+            if self.recent_hooks.len() == RECENT_HOOKS {
+                self.recent_hooks.pop_front();
             }
+            self.recent_hooks.push_back((self.bank(), self.pc));
+
+            hook(self);
         }
     }
 
+    /// Bank and address of the most recent hooks that ran, oldest first.
+    pub fn recent_hooks(&self) -> impl Iterator<Item = (usize, u16)> + '_ {
+        self.recent_hooks.iter().copied()
+    }
+
     pub fn jump(&mut self, pc: u16) {
         self.call(pc);
         self.pc = self.stack_pop();
@@ -156,10 +186,37 @@ impl Cpu {
 
     pub fn set_save_path(&mut self, path: path::PathBuf) {
         self.mmu.mbc.set_save_path(path);
+        self.autosave_triggers = AutosaveTriggers::default();
+    }
+
+    pub(crate) fn autosave_triggers_mut(&mut self) -> &mut AutosaveTriggers {
+        &mut self.autosave_triggers
     }
 
-    pub fn save_to_disk(&mut self) {
-        self.mmu.mbc.save_to_disk();
+    pub fn save_path(&self) -> Option<&path::Path> {
+        self.mmu.mbc.save_path()
+    }
+
+    /// Write SRAM to the save file, along with its metadata and thumbnail.
+    pub fn save_to_disk(&mut self) -> io::Result<()> {
+        let save_path = match self.save_path() {
+            Some(path) => path.to_owned(),
+            None => return Ok(()),
+        };
+
+        let new_save = !save_path.exists();
+        self.mmu.mbc.save_to_disk()?;
+
+        if let Err(e) = save_metadata::update_metadata(
+            &save_path,
+            self.borrow_sram(),
+            new_save,
+            self.mmu.gpu.frame(),
+        ) {
+            log::error!("Error writing metadata for {}: {}", save_path.display(), e);
+        }
+
+        Ok(())
     }
 
     pub fn gpu_push_layer(&mut self) -> usize {
@@ -170,6 +227,10 @@ impl Cpu {
         self.mmu.gpu.layer_pop(layer);
     }
 
+    pub fn gpu_has_layers(&self) -> bool {
+        self.mmu.gpu.has_layers()
+    }
+
     pub fn gpu_mut_layer(&mut self, layer: usize) -> &mut GpuLayer {
         self.mmu.gpu.layer_mut(layer)
     }
@@ -179,11 +240,54 @@ impl Cpu {
     }
 
     pub fn keypad_wait(&mut self) -> KeypadKey {
-        self.mmu.keypad.wait()
+        loop {
+            match self.mmu.keypad.wait() {
+                Ok(key) => return key,
+                Err(QuitRequested) => self.request_quit(),
+            }
+        }
     }
 
     pub fn keyboard_text(&mut self) -> TextEvent {
-        self.mmu.keypad.text()
+        loop {
+            match self.mmu.keypad.text() {
+                Ok(event) => return event,
+                Err(QuitRequested) => self.request_quit(),
+            }
+        }
+    }
+
+    pub fn keyboard_wait(&mut self) -> KeyboardKey {
+        loop {
+            match self.mmu.keypad.wait_keyboard() {
+                Ok(key) => return key,
+                Err(QuitRequested) => self.request_quit(),
+            }
+        }
+    }
+
+    /// The player asked to quit. Without a game in progress, or if they are
+    /// already being asked whether to save, that happens right away. Otherwise
+    /// the offer to save waits until saving is possible, see
+    /// [`Cpu::handle_hotkeys`].
+    fn request_quit(&mut self) {
+        if self.save_path().is_none() || self.quitting {
+            game::shutdown(ExitStatus::Quit);
+        }
+
+        if !self.quit_pending {
+            self.quit_pending = true;
+            show_toast("Close again to quit without saving");
+        }
+    }
+
+    /// Offer to save, and then stop the game. Asking to quit again while the
+    /// offer is showing quits without saving.
+    fn quit(&mut self) -> ! {
+        self.quitting = true;
+        save::save_before_quitting(self);
+
+        game::shutdown(ExitStatus::Quit)
     }
 
     pub fn start_music<T, TSource>(&mut self, music: T)
@@ -247,6 +351,10 @@ impl Cpu {
             return;
         }
 
+        // Halted means that the main loop is waiting for the next frame, so
+        // it isn't halfway through changing anything.
+        let waiting_for_frame = self.halted;
+
         self.halted = false;
         if !self.ime {
             return;
@@ -257,11 +365,62 @@ impl Cpu {
         assert!(n < 5, "Invalid interrupt triggered");
         self.mmu.intf &= !(1 << n);
 
+        if n == 0 {
+            let can_save = waiting_for_frame && save::is_overworld_idle(self);
+
+            self.handle_hotkeys(can_save);
+            self.mmu.sound2.update_volume();
+            autosave::check_autosave(self, can_save);
+        }
+
         let pc = self.pc;
         self.call(0x0040 | ((n as u16) << 3));
         self.pc = pc;
     }
 
+    /// Handle hotkeys that affect the game. This runs at the start of the
+    /// VBlank interrupt, so that it happens at most once per frame. Saving,
+    /// including the offer to save before quitting, waits until `can_save` is
+    /// set.
+    fn handle_hotkeys(&mut self, can_save: bool) {
+        if self.mmu.keypad.take_quit_request() {
+            self.request_quit();
+        }
+
+        if self.quit_pending && can_save {
+            self.quit();
+        }
+
+        for action in self.mmu.keypad.take_hotkeys() {
+            match action {
+                Action::Mute => {
+                    let muted = !self.mmu.sound2.is_muted();
+                    self.mmu.sound2.set_muted(muted);
+                    show_toast(if muted { "Muted" } else { "Unmuted" });
+                }
+                Action::QuickSave if self.save_path().is_none() => {
+                    show_toast("Nothing to save yet");
+                }
+                Action::QuickSave if !can_save => show_toast("Can't save right now"),
+                Action::QuickSave => {
+                    save::quick_save(self);
+                }
+                Action::Palette => {
+                    let palette = settings().palette.next();
+                    self.mmu.gpu.set_palette(&Palette::load(&palette));
+                    show_toast(format!("Palette {}", palette.to_string().replace('_', " ")));
+                    settings_mut().palette = palette;
+                }
+                Action::ShowFps => {
+                    let mut settings = settings_mut();
+                    settings.show_fps = !settings.show_fps;
+                }
+                // Handled by the frontend
+                _ => {}
+            }
+        }
+    }
+
     pub fn stack_push(&mut self, value: u16) {
         self.sp -= 2;
         self.mmu.ww(self.sp, value);
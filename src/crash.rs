@@ -0,0 +1,152 @@
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fmt::Write as _,
+    fs, io, panic,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{cpu::Cpu, game::engine::menus::save, saves};
+
+/// How many log lines to keep for the crash report.
+const RECENT_LOG_LINES: usize = 50;
+
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// The last panic message, location and backtrace, as recorded by the panic
+/// hook.
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Forwards to `env_logger`, while keeping the most recent lines at `Info` or
+/// above for the crash report, even if they aren't printed.
+struct Logger {
+    inner: env_logger::Logger,
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.level() <= log::Level::Info {
+            let line = format!("{} {}: {}", record.level(), record.target(), record.args());
+
+            if let Ok(mut recent) = RECENT_LOG.lock() {
+                if recent.len() == RECENT_LOG_LINES {
+                    recent.pop_front();
+                }
+                recent.push_back(line);
+            }
+        }
+
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Set up logging, configured by `RUST_LOG` like `env_logger::init`, and a
+/// panic hook that records what went wrong for the crash report.
+pub fn init_crash_reporting() {
+    let inner = env_logger::Builder::from_default_env().build();
+    let max_level = inner.filter().max(log::LevelFilter::Info);
+
+    if log::set_boxed_logger(Box::new(Logger { inner })).is_ok() {
+        log::set_max_level(max_level);
+    }
+
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        let report = format!("{}\n\n{}", info, Backtrace::force_capture());
+
+        if let Ok(mut last) = LAST_PANIC.lock() {
+            *last = Some(report);
+        }
+
+        default_hook(info);
+    }));
+}
+
+pub(crate) fn get_crash_dir() -> io::Result<PathBuf> {
+    Ok(saves::get_data_dir()?.join("crashes"))
+}
+
+/// Snapshot the game after it panicked: SRAM with the current progress copied
+/// in, followed by WRAM, next to the save file, and a crash report in the
+/// crashes directory.
+pub(crate) fn write_emergency_files(cpu: &mut Cpu) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let report = crash_report(cpu);
+
+    match write_crash_report(&report, timestamp) {
+        Ok(path) => log::error!("Crash report written to {}", path.display()),
+        Err(e) => log::error!("Error writing crash report: {}", e),
+    }
+
+    let save_path = match cpu.save_path() {
+        Some(path) => path.to_owned(),
+        None => return,
+    };
+
+    save::copy_progress_to_sram(cpu);
+    cpu.borrow_sram_mut().update_checksums();
+
+    let path = saves::get_emergency_save_path(&save_path, timestamp);
+    let data = [cpu.borrow_sram().as_bytes(), cpu.borrow_wram().as_bytes()].concat();
+
+    match fs::write(&path, data) {
+        Ok(()) => log::error!("Emergency save written to {}", path.display()),
+        Err(e) => log::error!("Error writing emergency save: {}", e),
+    }
+}
+
+fn write_crash_report(report: &str, timestamp: u64) -> io::Result<PathBuf> {
+    let dir = get_crash_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    let path = dir.join(format!("crash-{}.txt", timestamp));
+    fs::write(&path, report)?;
+
+    Ok(path)
+}
+
+fn crash_report(cpu: &Cpu) -> String {
+    let mut report = String::new();
+
+    let panic = LAST_PANIC.lock().ok().and_then(|mut last| last.take());
+    let _ = writeln!(report, "{}\n", panic.as_deref().unwrap_or("Unknown panic"));
+
+    let _ = writeln!(report, "Bank:PC  {:02x}:{:04x}", cpu.bank(), cpu.pc);
+    let _ = writeln!(
+        report,
+        "AF {:02x}{:02x}  BC {:02x}{:02x}  DE {:02x}{:02x}  HL {:02x}{:02x}  SP {:04x}",
+        cpu.a, cpu.f, cpu.b, cpu.c, cpu.d, cpu.e, cpu.h, cpu.l, cpu.sp
+    );
+    let _ = writeln!(report, "IME {}  halted {}", cpu.ime, cpu.halted);
+
+    let _ = writeln!(report, "\nRecent hooks, oldest first:");
+    for (bank, pc) in cpu.recent_hooks() {
+        let _ = writeln!(report, "  {:02x}:{:04x}", bank, pc);
+    }
+
+    let _ = writeln!(report, "\nRecent log lines:");
+    if let Ok(recent) = RECENT_LOG.lock() {
+        for line in recent.iter() {
+            let _ = writeln!(report, "  {}", line);
+        }
+    }
+
+    report
+}
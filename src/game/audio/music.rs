@@ -1,19 +1,40 @@
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{self, BufReader};
+use std::path::Path;
 
 use rodio::decoder::LoopedDecoder;
-use rodio::Decoder;
+use rodio::source::Empty;
+use rodio::{Decoder, Source};
 
 use crate::game::resources_root;
+use crate::settings::settings;
 use crate::sound2::{Music as MusicTrait, Sfx as SfxTrait};
 
-type MusicDecoder = LoopedDecoder<BufReader<File>>;
+type MusicDecoder = Box<dyn Source<Item = i16> + Send>;
 
+/// Open a track from `music_dir`, or from the bundled music if that fails.
+/// Nothing is played if neither has it.
 fn open_music(name: &str) -> MusicDecoder {
-    let root = resources_root().unwrap_or(std::env::current_dir().unwrap());
-    let file = File::open(root.join("music").join(name)).unwrap();
+    let bundled = resources_root()
+        .unwrap_or(std::env::current_dir().unwrap())
+        .join("music");
+    let dirs = settings().music_dir.clone().into_iter().chain([bundled]);
 
-    Decoder::new_looped(BufReader::new(file)).unwrap()
+    for dir in dirs {
+        let path = dir.join(name);
+
+        match open_looped(&path) {
+            Ok(decoder) => return Box::new(decoder),
+            Err(e) => log::error!("Error opening music {}: {}", path.display(), e),
+        }
+    }
+
+    Box::new(Empty::new())
+}
+
+fn open_looped(path: &Path) -> io::Result<LoopedDecoder<BufReader<File>>> {
+    let file = File::open(path)?;
+    Decoder::new_looped(BufReader::new(file)).map_err(io::Error::other)
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -0,0 +1,37 @@
+// Outdoor maps, whose names are shown on the town map
+pub const PALLET_TOWN: u8 = 0x00;
+pub const VIRIDIAN_CITY: u8 = 0x01;
+pub const PEWTER_CITY: u8 = 0x02;
+pub const CERULEAN_CITY: u8 = 0x03;
+pub const LAVENDER_TOWN: u8 = 0x04;
+pub const VERMILION_CITY: u8 = 0x05;
+pub const CELADON_CITY: u8 = 0x06;
+pub const FUCHSIA_CITY: u8 = 0x07;
+pub const CINNABAR_ISLAND: u8 = 0x08;
+pub const INDIGO_PLATEAU: u8 = 0x09;
+pub const SAFFRON_CITY: u8 = 0x0a;
+pub const ROUTE_1: u8 = 0x0c;
+pub const ROUTE_25: u8 = 0x24;
+
+pub const FIRST_INDOOR_MAP: u8 = 0x25;
+
+/// Name of an outdoor map, as on the town map.
+pub fn outdoor_map_name(map: u8) -> Option<String> {
+    let name = match map {
+        PALLET_TOWN => "PALLET TOWN",
+        VIRIDIAN_CITY => "VIRIDIAN CITY",
+        PEWTER_CITY => "PEWTER CITY",
+        CERULEAN_CITY => "CERULEAN CITY",
+        LAVENDER_TOWN => "LAVENDER TOWN",
+        VERMILION_CITY => "VERMILION CITY",
+        CELADON_CITY => "CELADON CITY",
+        FUCHSIA_CITY => "FUCHSIA CITY",
+        CINNABAR_ISLAND => "CINNABAR ISLAND",
+        INDIGO_PLATEAU => "INDIGO PLATEAU",
+        SAFFRON_CITY => "SAFFRON CITY",
+        ROUTE_1..=ROUTE_25 => return Some(format!("ROUTE {}", map - ROUTE_1 + 1)),
+        _ => return None,
+    };
+
+    Some(name.to_owned())
+}
@@ -2,3 +2,6 @@
 pub const TEXT_DELAY_FAST: u8 = 0b001; // 1
 pub const TEXT_DELAY_MEDIUM: u8 = 0b011; // 3
 pub const TEXT_DELAY_SLOW: u8 = 0b101; // 5
+
+pub const BIT_BATTLE_SHIFT: u8 = 6;
+pub const BIT_BATTLE_ANIMATION: u8 = 7;
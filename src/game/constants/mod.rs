@@ -3,9 +3,11 @@ pub mod gfx_constants;
 pub mod hardware_constants;
 pub mod input_constants;
 pub mod item_constants;
+pub mod map_constants;
 pub mod misc_constants;
 pub mod palette_constants;
 pub mod pikachu_emotion_constants;
 pub mod pokemon_constants;
 pub mod serial_constants;
 pub mod text_constants;
+pub mod tileset_constants;
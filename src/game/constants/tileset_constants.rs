@@ -0,0 +1 @@
+pub const POKECENTER: u8 = 0x06;
@@ -0,0 +1,132 @@
+use crate::{
+    bindings::Action,
+    cpu::Cpu,
+    game::{audio, home::text},
+    keypad::{KeyCapture, KeyboardKey},
+    settings::{settings, settings_mut, write_settings},
+};
+
+/// Characters that fit on a row of a full width menu, next to the cursor.
+const ROW_WIDTH: usize = 17;
+
+pub fn controls_menu(cpu: &mut Cpu) {
+    let mut selected = 0;
+    let layer = cpu.gpu_push_layer();
+
+    loop {
+        let actions: &[Action] = match super::menu_single_choice(
+            cpu,
+            layer,
+            &mut selected,
+            (0, 0),
+            &["JOYPAD", "SPEED", "OTHER"],
+        ) {
+            None => break,
+            Some(0) => &Action::JOYPAD,
+            Some(1) => &Action::SPEED,
+            Some(2) => &Action::OTHER,
+            _ => unreachable!(),
+        };
+
+        controls_menu_rebind(cpu, actions);
+    }
+
+    cpu.gpu_pop_layer(layer);
+
+    if let Err(error) = write_settings() {
+        log::error!("Error writing settings: {}", error);
+    }
+}
+
+fn controls_menu_rebind(cpu: &mut Cpu, actions: &[Action]) {
+    let mut selected = 0;
+    let layer = cpu.gpu_push_layer();
+
+    loop {
+        let choices = {
+            let settings = settings();
+            let bindings = &settings.key_bindings;
+
+            let label_width = actions.iter().map(|a| a.label().len()).max().unwrap_or(0) + 1;
+            let keys_width = ROW_WIDTH - label_width;
+
+            actions
+                .iter()
+                .map(|&action| {
+                    let keys = keys_label(bindings.keys_for(action), keys_width);
+                    format!("{:<label_width$}{:>keys_width$}", action.label(), keys)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let choices = choices.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let action = match super::menu_single_choice(cpu, layer, &mut selected, (0, 0), &choices) {
+            None => break,
+            Some(choice) => actions[choice],
+        };
+
+        if let Some(key) = wait_for_new_key(cpu, action) {
+            settings_mut().key_bindings.rebind(action, key);
+        }
+    }
+
+    cpu.gpu_pop_layer(layer);
+}
+
+/// The labels of `keys` separated by slashes, as many as fit in `width`. A `+`
+/// means that some keys were left out, and a `…` that even the first key was
+/// too long.
+fn keys_label(keys: &[KeyboardKey], width: usize) -> String {
+    let mut label = String::new();
+
+    for (idx, key) in keys.iter().enumerate() {
+        let key = key.label();
+        let candidate = if label.is_empty() {
+            key.clone()
+        } else {
+            format!("{}/{}", label, key)
+        };
+        let more = usize::from(idx + 1 < keys.len());
+
+        if candidate.len() + more <= width {
+            label = candidate;
+        } else if label.is_empty() {
+            label = format!("{}…", &key[..width - 1]);
+            break;
+        } else {
+            label.push('+');
+            break;
+        }
+    }
+
+    label
+}
+
+/// Ask the player to press the new key for `action`. Returns `None` if the
+/// player pressed Escape.
+fn wait_for_new_key(cpu: &mut Cpu, action: Action) -> Option<KeyboardKey> {
+    let _key_capture = KeyCapture::begin();
+    let layer = cpu.gpu_push_layer();
+
+    text::text_box_border(cpu.gpu_mut_layer(layer), 0, 12, 18, 4);
+    text::place_string(cpu.gpu_mut_layer(layer), 1, 14, "Press a key for");
+    text::place_string(
+        cpu.gpu_mut_layer(layer),
+        1,
+        16,
+        &format!("{}.", action.label()),
+    );
+
+    cpu.gpu_update_screen();
+
+    let key = match cpu.keyboard_wait() {
+        KeyboardKey::Escape => None,
+        key => Some(key),
+    };
+
+    cpu.play_sfx(audio::sfx::PRESS_AB);
+    cpu.gpu_pop_layer(layer);
+
+    key
+}
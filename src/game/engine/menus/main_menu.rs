@@ -2,13 +2,17 @@ use crate::{
     cpu::Cpu,
     game::{
         audio, constants,
-        engine::movie,
+        engine::{movie, pokemon},
         home,
         ram::{hram, vram, wram},
     },
-    keypad::{KeypadKey, TextEvent},
+    gpu::GpuLayer,
+    hud::show_toast,
+    keypad::{KeypadKey, TextEntry, TextEvent},
+    save_metadata::SaveMetadata,
     save_state::SaveState,
     saves,
+    settings::settings,
 };
 
 pub fn main_menu(cpu: &mut Cpu) {
@@ -16,15 +20,7 @@ pub fn main_menu(cpu: &mut Cpu) {
 
     cpu.write_byte(wram::W_OPTIONS_INITIALIZED, 0);
 
-    let has_saves = match saves::list_save_files() {
-        Ok(files) => !files.is_empty(),
-        Err(e) => {
-            log::error!("Error listing save files: {}", e);
-            false
-        }
-    };
-
-    cpu.write_byte(wram::W_SAVE_FILE_STATUS, if has_saves { 2 } else { 1 });
+    offer_emergency_restore(cpu);
 
     cpu.write_byte(
         wram::W_LINK_STATE,
@@ -45,24 +41,57 @@ pub fn main_menu(cpu: &mut Cpu) {
     }
 
     let mut selected = 0;
-    let layer = cpu.gpu_push_layer();
 
     loop {
-        let selected = super::menu_single_choice(
-            cpu,
-            layer,
-            &mut selected,
-            (0, 0),
-            &["CONTINUE", "NEW GAME"][if has_saves { 0.. } else { 1.. }],
-        );
+        let has_saves = match saves::list_save_files() {
+            Ok(files) => !files.is_empty(),
+            Err(e) => {
+                log::error!("Error listing save files: {}", e);
+                false
+            }
+        };
 
-        match (selected, has_saves) {
-            (None, _) => {
+        let has_imports = match saves::list_import_files() {
+            Ok(files) => !files.is_empty(),
+            Err(e) => {
+                log::error!("Error listing import files: {}", e);
+                false
+            }
+        };
+
+        cpu.write_byte(wram::W_SAVE_FILE_STATUS, if has_saves { 2 } else { 1 });
+
+        let mut entries = Vec::new();
+        if has_saves {
+            entries.push(MainMenuEntry::Continue);
+        }
+        entries.push(MainMenuEntry::NewGame);
+        if has_saves {
+            entries.push(MainMenuEntry::ManageSaves);
+        }
+        if has_imports {
+            entries.push(MainMenuEntry::Import);
+        }
+        entries.push(MainMenuEntry::Controls);
+
+        let labels = entries
+            .iter()
+            .map(|entry| entry.label())
+            .collect::<Vec<_>>();
+        selected = selected.min(entries.len() - 1);
+
+        // The entries change when saves are added or removed, so they're drawn
+        // on a fresh layer
+        let layer = cpu.gpu_push_layer();
+        let choice = super::menu_single_choice(cpu, layer, &mut selected, (0, 0), &labels);
+
+        match choice.map(|idx| entries[idx]) {
+            None => {
                 cpu.gpu_pop_layer(layer);
                 return;
             }
 
-            (Some(0), true) => {
+            Some(MainMenuEntry::Continue) => {
                 if main_menu_select_save(cpu) {
                     cpu.gpu_pop_layer(layer);
                     prepare_for_game(cpu);
@@ -70,7 +99,7 @@ pub fn main_menu(cpu: &mut Cpu) {
                 }
             }
 
-            (Some(0), false) | (Some(1), true) => {
+            Some(MainMenuEntry::NewGame) => {
                 if main_menu_new_game(cpu) {
                     cpu.gpu_pop_layer(layer);
                     prepare_for_game(cpu);
@@ -78,12 +107,106 @@ pub fn main_menu(cpu: &mut Cpu) {
                 }
             }
 
-            _ => unreachable!(),
+            Some(MainMenuEntry::ManageSaves) => {
+                main_menu_manage_saves(cpu);
+            }
+
+            Some(MainMenuEntry::Import) => {
+                main_menu_import(cpu);
+            }
+
+            Some(MainMenuEntry::Controls) => {
+                super::controls::controls_menu(cpu);
+            }
+        }
+
+        cpu.gpu_pop_layer(layer);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum MainMenuEntry {
+    Continue,
+    NewGame,
+    ManageSaves,
+    Import,
+    Controls,
+}
+
+impl MainMenuEntry {
+    fn label(self) -> &'static str {
+        match self {
+            MainMenuEntry::Continue => "CONTINUE",
+            MainMenuEntry::NewGame => "NEW GAME",
+            MainMenuEntry::ManageSaves => "MANAGE SAVES",
+            MainMenuEntry::Import => "IMPORT",
+            MainMenuEntry::Controls => "CONTROLS",
+        }
+    }
+}
+
+/// Offer to restore the snapshots written when the game crashed. Declined
+/// snapshots are moved to the crashes directory.
+fn offer_emergency_restore(cpu: &mut Cpu) {
+    let list = match saves::list_emergency_saves() {
+        Ok(files) => files,
+        Err(error) => {
+            log::error!("Error listing emergency saves: {}", error);
+            return;
+        }
+    };
+
+    for emergency in list {
+        let restore = super::menu_display_confirmation(
+            cpu,
+            &[
+                "The game crashed",
+                "while playing",
+                &format!("{}.", emergency.name),
+                "Restore unsaved",
+                "progress?",
+            ],
+        );
+
+        let result = if restore {
+            saves::restore_emergency_save(&emergency).map(|path| {
+                show_toast(format!("Restored {}", emergency.name));
+                log::info!("Restored {}", path.display());
+            })
+        } else {
+            saves::dismiss_emergency_save(&emergency)
+        };
+
+        if let Err(error) = result {
+            log::error!("Error handling {}: {}", emergency.path.display(), error);
         }
     }
 }
 
 fn main_menu_new_game(cpu: &mut Cpu) -> bool {
+    let name = match enter_save_name(cpu) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    let path = saves::create_save_dir().and_then(|()| saves::get_save_path(&name));
+
+    match path {
+        Ok(path) => {
+            cpu.set_save_path(path);
+            true
+        }
+        Err(error) => {
+            log::error!("Error creating the save directory: {}", error);
+            cpu.play_sfx(audio::sfx::DENIED);
+            false
+        }
+    }
+}
+
+/// Ask for the name of a new save, until a name that isn't taken is entered.
+fn enter_save_name(cpu: &mut Cpu) -> Option<String> {
+    let _text_entry = TextEntry::begin();
     let layer = cpu.gpu_push_layer();
 
     home::text::text_box_border(cpu.gpu_mut_layer(layer), 1, 2, 16, 6);
@@ -99,7 +222,7 @@ fn main_menu_new_game(cpu: &mut Cpu) -> bool {
 
         match event {
             TextEvent::Append(c) => {
-                if result.len() < 14 {
+                if result.len() < saves::MAX_SAVE_NAME_LEN {
                     result.push(c);
                 }
             }
@@ -110,7 +233,7 @@ fn main_menu_new_game(cpu: &mut Cpu) -> bool {
 
             TextEvent::Cancel => {
                 cpu.gpu_pop_layer(layer);
-                return false;
+                return None;
             }
 
             TextEvent::Submit => {
@@ -118,34 +241,111 @@ fn main_menu_new_game(cpu: &mut Cpu) -> bool {
                     continue;
                 }
 
-                if !saves::save_is_free(&result) {
+                if !saves::is_valid_save_name(&result) || !saves::save_is_free(&result) {
                     cpu.play_sfx(audio::sfx::DENIED);
                     continue;
                 }
 
-                saves::create_save_dir().unwrap();
+                cpu.gpu_pop_layer(layer);
+                return Some(result);
+            }
+        }
+    }
+}
+
+/// Rename, duplicate or delete save files.
+fn main_menu_manage_saves(cpu: &mut Cpu) {
+    let mut selected = 0;
+
+    loop {
+        let list = match saves::list_save_files() {
+            Ok(ref files) if files.is_empty() => return,
+            Ok(files) => files,
+            Err(error) => {
+                log::error!("Error listing save files: {}", error);
+                return;
+            }
+        };
+
+        // Like the main menu, the list changes after each action
+        let layer = cpu.gpu_push_layer();
+        selected = selected.min(list.len() - 1);
 
-                cpu.set_save_path(saves::get_save_path(&result));
+        let choice = super::menu_single_choice(
+            cpu,
+            layer,
+            &mut selected,
+            (0, 0),
+            &list
+                .iter()
+                .map(|save| save.name.as_ref())
+                .collect::<Vec<_>>()[..],
+        );
 
+        let name = match choice {
+            Some(choice) => &list[choice].name,
+            None => {
                 cpu.gpu_pop_layer(layer);
-                return true;
+                return;
+            }
+        };
+
+        let action_layer = cpu.gpu_push_layer();
+        let action = super::menu_single_choice(
+            cpu,
+            action_layer,
+            &mut 0,
+            (8, 8),
+            &["RENAME", "DUPLICATE", "DELETE"],
+        );
+        cpu.gpu_pop_layer(action_layer);
+
+        let result = match action {
+            Some(0) => enter_save_name(cpu)
+                .map(|new| saves::rename_save(name, &new).map(|()| format!("Renamed to {}", new))),
+            Some(1) => enter_save_name(cpu).map(|new| {
+                saves::duplicate_save(name, &new).map(|()| format!("Copied to {}", new))
+            }),
+            Some(2) => {
+                super::menu_display_confirmation(cpu, &["Delete the save", &format!("{}?", name)])
+                    .then(|| saves::delete_save(name).map(|()| format!("Deleted {}", name)))
             }
+            _ => None,
+        };
+
+        match result {
+            Some(Ok(message)) => show_toast(message),
+            Some(Err(error)) => {
+                log::error!("Error managing {}: {}", name, error);
+                cpu.play_sfx(audio::sfx::DENIED);
+            }
+            None => {}
         }
+
+        cpu.gpu_pop_layer(layer);
     }
 }
 
-fn main_menu_select_save(cpu: &mut Cpu) -> bool {
-    let list = match saves::list_save_files() {
-        Ok(ref files) if files.is_empty() => {
-            return false;
-        }
+/// Pick a file from the import directory and add it to the saves.
+fn main_menu_import(cpu: &mut Cpu) {
+    let list = match saves::list_import_files() {
         Ok(files) => files,
         Err(error) => {
-            log::error!("Error listing save files: {}", error);
-            return false;
+            log::error!("Error listing import files: {}", error);
+            return;
         }
     };
 
+    let entries = list
+        .iter()
+        .map(|path| {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            stem.chars()
+                .take(saves::MAX_SAVE_NAME_LEN)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+
     let mut selected = 0;
     let layer = cpu.gpu_push_layer();
 
@@ -155,12 +355,100 @@ fn main_menu_select_save(cpu: &mut Cpu) -> bool {
             layer,
             &mut selected,
             (0, 0),
-            &list
+            &entries
                 .iter()
-                .map(|save| save.name.as_ref())
+                .map(|entry| entry.as_ref())
                 .collect::<Vec<_>>()[..],
         );
 
+        let path = match selected {
+            Some(selected) => &list[selected],
+            None => {
+                cpu.gpu_pop_layer(layer);
+                return;
+            }
+        };
+
+        let name = match enter_save_name(cpu) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        match saves::import_save(path, &name) {
+            Ok(_) => {
+                show_toast(format!("Imported {}", name));
+                cpu.gpu_pop_layer(layer);
+                return;
+            }
+            Err(error) => {
+                log::error!("Error importing {}: {}", path.display(), error);
+                cpu.play_sfx(audio::sfx::DENIED);
+                super::menu_display_text(cpu, &["This file isn't", "a valid save."]);
+            }
+        }
+    }
+}
+
+fn main_menu_select_save(cpu: &mut Cpu) -> bool {
+    let list = match saves::list_save_files() {
+        Ok(ref files) if files.is_empty() => {
+            return false;
+        }
+        Ok(files) => files,
+        Err(error) => {
+            log::error!("Error listing save files: {}", error);
+            return false;
+        }
+    };
+
+    // Each save is followed by its autosaves, most recent first. Any beyond
+    // the number of slots are removed by the next autosave.
+    let slots = settings().autosave.slots as usize;
+    let choices = list
+        .iter()
+        .flat_map(|save| {
+            std::iter::once((save, None)).chain(
+                (1..)
+                    .zip(saves::list_autosaves(&save.path))
+                    .take(slots)
+                    .map(move |autosave| (save, Some(autosave))),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Saves that can't be read or fail the game's checksums are marked with ×
+    let (entries, previews): (Vec<_>, Vec<_>) = choices
+        .iter()
+        .map(|(save, autosave)| {
+            let (name, path) = match autosave {
+                Some((slot, path)) => (format!("{} A{}", save.name, slot), path),
+                None => (save.name.clone(), &save.path),
+            };
+
+            match SaveState::from_file(path) {
+                Ok(data) => {
+                    let metadata = match autosave {
+                        Some(_) => SaveMetadata::from_save(&data),
+                        None => SaveMetadata::load_or_from_save(&save.path, &data),
+                    };
+
+                    if data.checksums_valid() {
+                        (name, Some(metadata))
+                    } else {
+                        (format!("×{}", name), Some(metadata))
+                    }
+                }
+                Err(_) => (format!("×{}", name), None),
+            }
+        })
+        .unzip();
+
+    let mut selected = 0;
+    let layer = cpu.gpu_push_layer();
+
+    loop {
+        let selected = pick_save(cpu, layer, &mut selected, &entries, &previews);
+
         match selected {
             None => {
                 cpu.gpu_pop_layer(layer);
@@ -168,10 +456,36 @@ fn main_menu_select_save(cpu: &mut Cpu) -> bool {
             }
 
             Some(selected) => {
-                let save = &list[selected];
-                let data = SaveState::from_file(&save.path).unwrap();
-
-                if display_continue_game_info(cpu, &data) {
+                let (save, autosave) = &choices[selected];
+
+                let (data, metadata) = match autosave {
+                    Some((_, path)) => match SaveState::from_file(path) {
+                        Ok(data) if data.checksums_valid() => {
+                            let metadata = SaveMetadata::from_save(&data);
+                            (data, metadata)
+                        }
+                        _ => {
+                            log::error!("Can't continue from {}", path.display());
+                            cpu.play_sfx(audio::sfx::DENIED);
+                            continue;
+                        }
+                    },
+                    None => {
+                        let data = match load_save_or_backup(cpu, save) {
+                            Some(data) => data,
+                            None => {
+                                cpu.play_sfx(audio::sfx::DENIED);
+                                continue;
+                            }
+                        };
+                        let metadata = SaveMetadata::load_or_from_save(&save.path, &data);
+                        (data, metadata)
+                    }
+                };
+
+                // Continuing from an autosave keeps saving to the save it
+                // belongs to
+                if display_continue_game_info(cpu, &data, &metadata) {
                     cpu.replace_ram(data);
                     cpu.set_save_path(save.path.clone());
                     super::save::load_sav(cpu);
@@ -183,11 +497,148 @@ fn main_menu_select_save(cpu: &mut Cpu) -> bool {
     }
 }
 
-fn display_continue_game_info(cpu: &mut Cpu, data: &SaveState) -> bool {
+/// Read a save to continue from, offering to restore a backup if it is
+/// damaged.
+fn load_save_or_backup(cpu: &mut Cpu, save: &saves::SaveFile) -> Option<SaveState> {
+    match SaveState::from_file(&save.path) {
+        Ok(data) if data.checksums_valid() => Some(data),
+        Ok(data) => {
+            log::warn!(
+                "{} has invalid checksums: {:?}",
+                save.path.display(),
+                data.invalid_checksums()
+            );
+
+            // Continuing anyway repairs the checksums on the next save
+            Some(offer_backup_restore(cpu, save).unwrap_or(data))
+        }
+        Err(error) => {
+            log::error!("Error reading {}: {}", save.path.display(), error);
+            offer_backup_restore(cpu, save)
+        }
+    }
+}
+
+/// How many saves fit in the list above the preview.
+const SAVE_LIST_HEIGHT: usize = 4;
+
+/// Let the player pick a save, with a preview of the party and location of
+/// the one under the cursor.
+fn pick_save(
+    cpu: &mut Cpu,
+    layer: usize,
+    selected: &mut usize,
+    entries: &[String],
+    previews: &[Option<SaveMetadata>],
+) -> Option<usize> {
+    let mut scroll_pos = selected.saturating_sub(SAVE_LIST_HEIGHT - 1);
+
+    loop {
+        home::text::text_box_border(cpu.gpu_mut_layer(layer), 0, 0, 18, SAVE_LIST_HEIGHT * 2);
+
+        let window = scroll_pos..usize::min(scroll_pos + SAVE_LIST_HEIGHT, entries.len());
+
+        for (i, idx) in window.enumerate() {
+            let y = 2 + i * 2;
+
+            if idx == *selected {
+                home::text::place_char(cpu.gpu_mut_layer(layer), 1, y, '▶');
+            }
+
+            home::text::place_string(cpu.gpu_mut_layer(layer), 2, y, &entries[idx]);
+        }
+
+        if scroll_pos + SAVE_LIST_HEIGHT < entries.len() {
+            home::text::place_char(cpu.gpu_mut_layer(layer), 18, SAVE_LIST_HEIGHT * 2, '▼');
+        }
+
+        draw_save_preview(cpu.gpu_mut_layer(layer), 11, previews[*selected].as_ref());
+
+        cpu.gpu_update_screen();
+
+        match cpu.keypad_wait() {
+            KeypadKey::A => {
+                cpu.play_sfx(audio::sfx::PRESS_AB);
+                break Some(*selected);
+            }
+
+            KeypadKey::B => {
+                cpu.play_sfx(audio::sfx::PRESS_AB);
+                break None;
+            }
+
+            KeypadKey::Up if *selected > 0 => {
+                *selected -= 1;
+                scroll_pos = usize::min(scroll_pos, *selected);
+            }
+
+            KeypadKey::Down if *selected + 1 < entries.len() => {
+                *selected += 1;
+                if *selected >= scroll_pos + SAVE_LIST_HEIGHT {
+                    scroll_pos += 1;
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+/// Draw a box at row `y` with the location and party icons from a save's
+/// metadata, leaving it empty if there is none.
+fn draw_save_preview(layer: &mut GpuLayer, y: usize, metadata: Option<&SaveMetadata>) {
+    home::text::text_box_border(layer, 0, y, 18, 5);
+
+    let metadata = match metadata {
+        Some(metadata) => metadata,
+        None => return,
+    };
+
+    if let Some(ref location) = metadata.location {
+        home::text::place_string(layer, 1, y + 2, location);
+    }
+
+    for (i, member) in metadata.party.iter().enumerate() {
+        pokemon::place_pokemon_icon(layer, 1 + i * 3, y + 4, member.species);
+    }
+}
+
+/// Offer to replace a damaged save file with its most recent good backup,
+/// returning the restored save.
+fn offer_backup_restore(cpu: &mut Cpu, save: &saves::SaveFile) -> Option<SaveState> {
+    let (path, data) = saves::list_good_backups(&save.path).into_iter().next()?;
+
+    let restore = super::menu_display_confirmation(
+        cpu,
+        &[
+            "This save file",
+            "is damaged.",
+            "Restore the last",
+            "backup?",
+        ],
+    );
+
+    if !restore {
+        return None;
+    }
+
+    if let Err(error) = data.write_to_file(&save.path) {
+        log::error!("Error restoring {}: {}", path.display(), error);
+        return None;
+    }
+
+    show_toast(format!("Restored {}", save.name));
+
+    Some(data)
+}
+
+fn display_continue_game_info(cpu: &mut Cpu, data: &SaveState, metadata: &SaveMetadata) -> bool {
     let summary = super::save::load_sav_summary(data);
 
     let layer = cpu.gpu_push_layer();
 
+    draw_save_preview(cpu.gpu_mut_layer(layer), 0, Some(metadata));
+
     home::text::text_box_border(cpu.gpu_mut_layer(layer), 4, 7, 14, 8);
 
     home::text::place_string(cpu.gpu_mut_layer(layer), 5, 9, "PLAYER");
@@ -250,10 +701,7 @@ pub fn init_options(cpu: &mut Cpu) {
         wram::W_LETTER_PRINTING_DELAY_FLAGS,
         constants::misc_constants::TEXT_DELAY_FAST,
     );
-    cpu.write_byte(
-        wram::W_OPTIONS,
-        constants::misc_constants::TEXT_DELAY_MEDIUM,
-    );
+    cpu.write_byte(wram::W_OPTIONS, settings().game_options.to_byte());
     cpu.write_byte(wram::W_PRINTER_SETTINGS, 64); // audio?
 }
 
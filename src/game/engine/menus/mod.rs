@@ -4,6 +4,7 @@ use crate::{
     keypad::KeypadKey,
 };
 
+pub mod controls;
 pub mod main_menu;
 pub mod pokedex;
 pub mod save;
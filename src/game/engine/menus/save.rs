@@ -1,9 +1,12 @@
+use std::io;
+
 use crate::{
     cpu::Cpu,
     game::{
         constants, home,
         ram::{hram, sram, wram},
     },
+    hud::show_toast,
     save_state::{PokeString, SaveState},
 };
 
@@ -93,11 +96,161 @@ pub fn save_sav_to_sram(cpu: &mut Cpu) {
     cpu.call(0x7b32); // SaveSAVtoSRAM1
     cpu.call(0x7b56); // SaveSAVtoSRAM2
 
-    cpu.save_to_disk();
+    let result = cpu.save_to_disk();
+    show_save_result(cpu, result);
 
     cpu.pc = cpu.stack_pop();
 }
 
+/// Whether the player is standing around in the overworld, with no battle,
+/// script, text box or menu in progress. Together with the main loop waiting
+/// for the next frame, this is when saving from outside the START menu gives
+/// the same result as saving from it.
+pub fn is_overworld_idle(cpu: &mut Cpu) -> bool {
+    /// `wd730` bits for scripted movement and simulated joypad presses.
+    const SCRIPTED_MOVEMENT: u8 = (1 << 0) | (1 << 7);
+    /// `hWY` when the window, and so any text box or menu, is hidden.
+    const WINDOW_HIDDEN: u8 = 144;
+
+    cpu.save_path().is_some()
+        && !cpu.gpu_has_layers()
+        && cpu.read_byte(wram::W_IS_IN_BATTLE) == 0
+        && cpu.read_byte(wram::W_LINK_STATE) == 0
+        && cpu.read_byte(wram::W_JOY_IGNORE) == 0
+        && cpu.read_byte(wram::W_D730) & SCRIPTED_MOVEMENT == 0
+        && cpu.read_byte(hram::H_WY) >= WINDOW_HIDDEN
+}
+
+/// Save the current progress without going through the START menu. This must
+/// only be called at a safe point, see [`is_overworld_idle`].
+pub fn quick_save(cpu: &mut Cpu) -> bool {
+    if cpu.save_path().is_none() {
+        log::warn!("Not saving, no game has been started yet");
+        return false;
+    }
+
+    cpu.write_byte(wram::W_SAVE_FILE_STATUS, 2);
+    run_save_sav_to_sram(cpu);
+
+    let result = cpu.save_to_disk();
+    show_save_result(cpu, result)
+}
+
+/// Run the game's own `SaveSAVtoSRAM` from in between two instructions of
+/// other code, e.g. from the VBlank interrupt. The registers and ROM bank of
+/// the interrupted code are restored afterwards.
+pub fn run_save_sav_to_sram(cpu: &mut Cpu) {
+    const SAVE_SAV_TO_SRAM_BANK: u8 = 0x1c;
+
+    let registers = (cpu.a, cpu.f, cpu.b, cpu.c, cpu.d, cpu.e, cpu.h, cpu.l);
+    let (pc, bank) = (cpu.pc, cpu.bank());
+
+    cpu.write_byte(
+        constants::hardware_constants::MBC1_ROM_BANK,
+        SAVE_SAV_TO_SRAM_BANK,
+    );
+
+    cpu.call(0x7ae5); // SaveSAVtoSRAM0
+    cpu.call(0x7b32); // SaveSAVtoSRAM1
+    cpu.call(0x7b56); // SaveSAVtoSRAM2
+
+    cpu.write_byte(constants::hardware_constants::MBC1_ROM_BANK, bank as u8);
+    (cpu.a, cpu.f, cpu.b, cpu.c, cpu.d, cpu.e, cpu.h, cpu.l) = registers;
+    cpu.pc = pc;
+}
+
+/// Copy the same data as `SaveSAVtoSRAM` from WRAM into SRAM, without writing
+/// it to disk. This bypasses the emulated MBC and doesn't run any game code,
+/// so it works even when the game can't continue, but WRAM may be halfway
+/// through an update.
+pub fn copy_progress_to_sram(cpu: &mut Cpu) {
+    copy_wram_to_sram(
+        cpu,
+        wram::W_PLAYER_NAME,
+        sram::S_PLAYER_NAME,
+        constants::text_constants::NAME_LENGTH as u16,
+    );
+
+    copy_wram_to_sram(
+        cpu,
+        wram::W_MAIN_DATA_START,
+        sram::S_MAIN_DATA,
+        wram::W_MAIN_DATA_END - wram::W_MAIN_DATA_START,
+    );
+
+    copy_wram_to_sram(
+        cpu,
+        wram::W_SPRITE_DATA_START,
+        sram::S_SPRITE_DATA,
+        wram::W_SPRITE_DATA_END - wram::W_SPRITE_DATA_START,
+    );
+
+    {
+        let v = cpu.read_byte(hram::H_TILE_ANIMATIONS);
+        cpu.borrow_sram_mut()
+            .set_byte(sram_offset(sram::S_TILE_ANIMATIONS), v);
+    }
+
+    copy_wram_to_sram(
+        cpu,
+        wram::W_BOX_DATA_START,
+        sram::S_CUR_BOX_DATA,
+        wram::W_BOX_DATA_END - wram::W_BOX_DATA_START,
+    );
+
+    copy_wram_to_sram(
+        cpu,
+        wram::W_PARTY_DATA_START,
+        sram::S_PARTY_DATA,
+        wram::W_PARTY_DATA_END - wram::W_PARTY_DATA_START,
+    );
+}
+
+/// Ask whether to save before quitting, if there is a game in progress. Like
+/// [`quick_save`], this must only be called at a safe point.
+pub fn save_before_quitting(cpu: &mut Cpu) {
+    if cpu.save_path().is_none() {
+        return;
+    }
+
+    if super::menu_display_confirmation(cpu, &["Save before", "quitting?"]) {
+        quick_save(cpu);
+    }
+}
+
+/// Tell the player whether writing the save file worked, returning whether it
+/// did.
+fn show_save_result(cpu: &Cpu, result: io::Result<()>) -> bool {
+    let name = match cpu.save_path().and_then(|path| path.file_stem()) {
+        Some(name) => name.to_string_lossy(),
+        None => return result.is_ok(),
+    };
+
+    match result {
+        Ok(()) => {
+            show_toast(format!("Saved to {}", name));
+            true
+        }
+        Err(e) => {
+            log::error!("Error saving {}: {}", name, e);
+            show_toast(format!("Error saving {}", name));
+            false
+        }
+    }
+}
+
+/// Offset into [`SaveState`] of an address in SRAM bank 1.
+fn sram_offset(addr: u16) -> usize {
+    0x2000 + (addr as usize - 0xa000)
+}
+
+fn copy_wram_to_sram(cpu: &mut Cpu, src: u16, dst: u16, count: u16) {
+    for idx in 0..count {
+        let byte = cpu.read_byte(src + idx);
+        cpu.borrow_sram_mut().set_byte(sram_offset(dst + idx), byte);
+    }
+}
+
 pub fn enable_sram_and_latch_clock_data(cpu: &mut Cpu) {
     cpu.write_byte(constants::hardware_constants::MBC1_SRAM_BANKING_MODE, 1);
     cpu.write_byte(
@@ -124,25 +124,13 @@ fn pick_pokemon(cpu: &mut Cpu, pos: (usize, usize), choices: &[BoxedPokemon]) ->
         let window = &choices[scroll_pos..usize::min(scroll_pos + window_height, choices.len())];
 
         for (i, choice) in window.iter().enumerate() {
-            let offset = (choice.species as usize) - 1;
-            let offset_x = (offset % 16) * 2;
-            let offset_y = (offset / 16) * 2;
-            let icon = (
-                GpuTile::new(GpuAtlas::PokemonIcons, offset_x, offset_y),
-                GpuTile::new(GpuAtlas::PokemonIcons, offset_x + 1, offset_y),
-                GpuTile::new(GpuAtlas::PokemonIcons, offset_x, offset_y + 1),
-                GpuTile::new(GpuAtlas::PokemonIcons, offset_x + 1, offset_y + 1),
+            super::place_pokemon_icon(
+                cpu.gpu_mut_layer(layer),
+                pos.0 + 2,
+                pos.1 + 1 + i * 3,
+                choice.species,
             );
 
-            cpu.gpu_mut_layer(layer)
-                .set_background(pos.0 + 2, pos.1 + 1 + i * 3, icon.0);
-            cpu.gpu_mut_layer(layer)
-                .set_background(pos.0 + 3, pos.1 + 1 + i * 3, icon.1);
-            cpu.gpu_mut_layer(layer)
-                .set_background(pos.0 + 2, pos.1 + 2 + i * 3, icon.2);
-            cpu.gpu_mut_layer(layer)
-                .set_background(pos.0 + 3, pos.1 + 2 + i * 3, icon.3);
-
             if scroll_pos + i == selected {
                 cpu.gpu_mut_layer(layer).set_background(
                     pos.0 + 1,
@@ -1 +1,24 @@
+use crate::{
+    gpu::{GpuAtlas, GpuLayer, GpuTile},
+    save_state::PokemonSpecies,
+};
+
 pub mod bills_pc;
+
+/// Draw the 2×2 tile menu icon of `species` with its top left corner at
+/// (`x`, `y`).
+pub fn place_pokemon_icon(layer: &mut GpuLayer, x: usize, y: usize, species: PokemonSpecies) {
+    let offset = (species as usize) - 1;
+    let offset_x = (offset % 16) * 2;
+    let offset_y = (offset / 16) * 2;
+
+    for dy in 0..2 {
+        for dx in 0..2 {
+            layer.set_background(
+                x + dx,
+                y + dy,
+                GpuTile::new(GpuAtlas::PokemonIcons, offset_x + dx, offset_y + dy),
+            );
+        }
+    }
+}
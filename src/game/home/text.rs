@@ -1,6 +1,10 @@
 use crate::{
-    gpu::{GpuAtlas, GpuLayer, GpuTile},
-    save_state::PokeString,
+    assets::atlases,
+    gpu::{GpuAtlas, GpuLayer, GpuSprite, GpuTile},
+    save_state::{
+        charmap::{self, CharmapEntry, Glyph},
+        PokeString,
+    },
 };
 
 const BOX_TOP_LEFT: GpuTile = GpuTile::new(GpuAtlas::BoxBorder, 0, 0);
@@ -38,36 +42,79 @@ pub fn text_box_border(layer: &mut GpuLayer, x: usize, y: usize, mut w: usize, m
     }
 }
 
-pub fn place_char(layer: &mut GpuLayer, x: usize, y: usize, chr: char) {
+fn glyph_tile(glyph: Glyph) -> GpuTile {
+    match glyph {
+        Glyph::Font(x, y) => GpuTile::new(GpuAtlas::Font, x, y),
+        Glyph::BoxBorder(x, y) => GpuTile::new(GpuAtlas::BoxBorder, x, y),
+    }
+}
+
+/// Characters that are in the font but not in the game's own charmap.
+fn extra_char_tile(chr: char) -> Option<GpuTile> {
     let tile = match chr {
-        'A'..='P' => GpuTile::new(GpuAtlas::Font, (chr as usize) - ('A' as usize), 0),
-        'Q'..='Z' => GpuTile::new(GpuAtlas::Font, (chr as usize) - ('Q' as usize), 1),
-        ':' => GpuTile::new(GpuAtlas::Font, 12, 1),
-        ';' => GpuTile::new(GpuAtlas::Font, 13, 1),
-        'a'..='p' => GpuTile::new(GpuAtlas::Font, (chr as usize) - ('a' as usize), 2),
-        'q'..='z' => GpuTile::new(GpuAtlas::Font, (chr as usize) - ('q' as usize), 3),
-        'é' => GpuTile::new(GpuAtlas::Font, 10, 3),
-        ' ' => GpuTile::new(GpuAtlas::Font, 15, 3),
+        'Ä' => GpuTile::new(GpuAtlas::Font, 0, 4),
+        'Ö' => GpuTile::new(GpuAtlas::Font, 1, 4),
+        'Ü' => GpuTile::new(GpuAtlas::Font, 2, 4),
+        'ä' => GpuTile::new(GpuAtlas::Font, 3, 4),
+        'ö' => GpuTile::new(GpuAtlas::Font, 4, 4),
+        'ü' => GpuTile::new(GpuAtlas::Font, 5, 4),
         '𝗟' => GpuTile::new(GpuAtlas::Font, 14, 5),
-        '\'' => GpuTile::new(GpuAtlas::Font, 0, 6),
-        '𝔭' => GpuTile::new(GpuAtlas::Font, 1, 6),
-        '𝔪' => GpuTile::new(GpuAtlas::Font, 2, 6),
-        '-' => GpuTile::new(GpuAtlas::Font, 3, 6),
-        '?' => GpuTile::new(GpuAtlas::Font, 6, 6),
-        '!' => GpuTile::new(GpuAtlas::Font, 7, 6),
-        '.' => GpuTile::new(GpuAtlas::Font, 8, 6),
-        '▷' => GpuTile::new(GpuAtlas::Font, 12, 6),
-        '▶' => GpuTile::new(GpuAtlas::Font, 13, 6),
-        '♂' => GpuTile::new(GpuAtlas::Font, 15, 6),
-        '¥' => GpuTile::new(GpuAtlas::Font, 0, 7),
-        '/' => GpuTile::new(GpuAtlas::Font, 3, 7),
-        ',' => GpuTile::new(GpuAtlas::Font, 4, 7),
-        '♀' => GpuTile::new(GpuAtlas::Font, 5, 7),
-        '0'..='9' => GpuTile::new(GpuAtlas::Font, (chr as usize) - ('0' as usize) + 6, 7),
-        _ => panic!("Invalid character: {}", chr),
+        '←' => GpuTile::new(GpuAtlas::Font, 15, 5),
+        '&' => GpuTile::new(GpuAtlas::Font, 9, 6),
+        '→' => GpuTile::new(GpuAtlas::Font, 11, 6),
+        _ => return None,
     };
 
-    layer.set_background(x, y, tile);
+    Some(tile)
+}
+
+/// The tile the asset pack draws `chr` with, if it overrides it.
+fn asset_pack_tile(chr: char) -> Option<GpuTile> {
+    let (x, y) = atlases().get(GpuAtlas::Font).glyph(chr)?;
+    Some(GpuTile::new(GpuAtlas::Font, x, y))
+}
+
+fn char_tile(chr: char) -> Option<GpuTile> {
+    if let Some(tile) = asset_pack_tile(chr) {
+        return Some(tile);
+    }
+
+    match charmap::lookup_char(chr).and_then(|entry| entry.glyph) {
+        Some(glyph) => Some(glyph_tile(glyph)),
+        None => extra_char_tile(chr),
+    }
+}
+
+/// The tiles that draw `byte`, expanding ligatures and words such as `POKé`.
+/// Single characters use the asset pack's tile first, the same as in
+/// [`place_char`].
+fn byte_tiles(byte: u8) -> Vec<GpuTile> {
+    match charmap::lookup_byte(byte) {
+        Some(CharmapEntry {
+            text,
+            glyph: Some(glyph),
+            ..
+        }) => {
+            let mut chars = text.chars();
+            let override_tile = match (chars.next(), chars.next()) {
+                (Some(chr), None) => asset_pack_tile(chr),
+                _ => None,
+            };
+
+            vec![override_tile.unwrap_or(glyph_tile(*glyph))]
+        }
+        Some(entry) => entry.text.chars().map(char_or_replacement_tile).collect(),
+        None => vec![glyph_tile(charmap::REPLACEMENT_GLYPH)],
+    }
+}
+
+fn char_or_replacement_tile(chr: char) -> GpuTile {
+    char_tile(chr).unwrap_or(glyph_tile(charmap::REPLACEMENT_GLYPH))
+}
+
+/// Draw `chr` at `x`, `y`, or a replacement glyph if it isn't in the font.
+pub fn place_char(layer: &mut GpuLayer, x: usize, y: usize, chr: char) {
+    layer.set_background(x, y, char_or_replacement_tile(chr));
 }
 
 pub fn place_string(layer: &mut GpuLayer, x: usize, y: usize, string: &str) {
@@ -76,18 +123,24 @@ pub fn place_string(layer: &mut GpuLayer, x: usize, y: usize, string: &str) {
     }
 }
 
+/// Place `string` with its top left corner at `x`, `y` in screen pixels,
+/// instead of on the tile grid. Returns the indices of the added sprites.
+pub fn place_string_at(layer: &mut GpuLayer, x: i32, y: i32, string: &str) -> Vec<usize> {
+    string
+        .chars()
+        .enumerate()
+        .map(|(idx, chr)| {
+            let tile = char_or_replacement_tile(chr);
+            layer.push_sprite(GpuSprite::from_tile(tile, x + (idx as i32) * 32, y))
+        })
+        .collect()
+}
+
 pub fn place_poke_string(layer: &mut GpuLayer, mut x: usize, y: usize, string: &PokeString) {
     for byte in string {
-        match byte {
-            0x80..=0xff => {
-                let offset = (byte as usize) - 0x80;
-                let tile = GpuTile::new(GpuAtlas::Font, offset % 16, offset / 16);
-
-                layer.set_background(x, y, tile);
-                x += 1;
-            }
-
-            _ => todo!("poke string: {:02x}", byte),
+        for tile in byte_tiles(byte) {
+            layer.set_background(x, y, tile);
+            x += 1;
         }
     }
 }
@@ -1,9 +1,10 @@
 use std::{
+    panic::{self, AssertUnwindSafe},
     path::PathBuf,
     sync::mpsc::{Receiver, SyncSender},
 };
 
-use crate::{cpu::Cpu, keypad::KeyboardEvent, rom::ROM, PokemonSpecies};
+use crate::{cpu::Cpu, crash, keypad::KeyboardEvent, rom::ROM};
 
 pub mod audio;
 pub mod constants;
@@ -31,6 +32,24 @@ pub fn resources_root() -> Option<PathBuf> {
     None
 }
 
+/// How the game stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// The player quit, after being offered to save.
+    Quit,
+    /// The frontend went away without asking the game to quit.
+    Disconnected,
+}
+
+/// Unwound through the game to get back to [`Game::boot`].
+struct Shutdown(ExitStatus);
+
+/// Stop the game from anywhere in the game thread, by unwinding the stack
+/// back to [`Game::boot`].
+pub fn shutdown(status: ExitStatus) -> ! {
+    panic::resume_unwind(Box::new(Shutdown(status)))
+}
+
 pub struct Game {
     cpu: Cpu,
 }
@@ -39,19 +58,37 @@ impl Game {
     pub fn new(
         update_screen: SyncSender<Vec<u8>>,
         keyboard_events: Receiver<KeyboardEvent>,
-        starter: PokemonSpecies,
     ) -> Self {
         assert_eq!(ROM[0x143], 0x80);
         assert_eq!(ROM[0x147], 0x1b);
         assert_eq!(ROM[0x149], 0x03);
 
         Self {
-            cpu: Cpu::new(update_screen, keyboard_events, starter),
+            cpu: Cpu::new(update_screen, keyboard_events),
         }
     }
 
-    pub fn boot(&mut self) {
-        self.cpu.call(0x0100)
+    /// Run the game until it is stopped by [`shutdown`].
+    pub fn boot(&mut self) -> ExitStatus {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.cpu.call(0x0100)));
+
+        match result {
+            Ok(()) => ExitStatus::Quit,
+            Err(payload) => match payload.downcast::<Shutdown>() {
+                Ok(shutdown) => shutdown.0,
+                Err(payload) => {
+                    let cpu = &mut self.cpu;
+                    let written =
+                        panic::catch_unwind(AssertUnwindSafe(|| crash::write_emergency_files(cpu)));
+
+                    if written.is_err() {
+                        log::error!("Panicked again while writing the emergency save");
+                    }
+
+                    panic::resume_unwind(payload)
+                }
+            },
+        }
     }
 
     pub fn sync_audio(&mut self) {
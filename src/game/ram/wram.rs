@@ -87,6 +87,12 @@ pub const W_LAST_MUSIC_SOUND_ID: u16 = 0xcfc9;
 
 pub const W_BATTLE_MON_SPECIES2: u16 = 0xcfd8;
 
+/// 0 if no battle \
+/// 1 if wild battle \
+/// 2 if trainer battle \
+/// -1 if lost battle
+pub const W_IS_IN_BATTLE: u16 = 0xd056;
+
 /// in a wild battle, this is the species of pokemon \
 /// in a trainer battle, this is the trainer class + OPP_ID_OFFSET
 pub const W_CUR_OPPONENT: u16 = 0xd058;
@@ -165,6 +171,8 @@ pub const W_LETTER_PRINTING_DELAY_FLAGS: u16 = 0xd357;
 
 pub const W_PLAYER_ID: u16 = 0xd358;
 
+pub const W_CUR_MAP: u16 = 0xd35d;
+
 pub const W_CUR_MAP_TILESET: u16 = 0xd366;
 
 pub const W_PRINTER_SETTINGS: u16 = 0xd497;
@@ -1,14 +1,17 @@
 use crate::{
     cpu::Cpu,
     game::{constants::item_constants, ram::wram},
+    settings::settings,
 };
 
 pub fn oaks_lab_text18(cpu: &mut Cpu) {
-    cpu.write_byte(wram::W_PLAYER_STARTER, cpu.starter.into_index());
-    cpu.write_byte(wram::W_D11E, cpu.starter.into_index());
+    let starter = settings().starter;
+
+    cpu.write_byte(wram::W_PLAYER_STARTER, starter.into_index());
+    cpu.write_byte(wram::W_D11E, starter.into_index());
 
     {
-        let source = cpu.starter.name();
+        let source = starter.name();
         let target = wram::W_CD6D;
 
         for (idx, byte) in source.iter().enumerate() {
@@ -37,10 +40,10 @@ pub fn oaks_lab_text18(cpu: &mut Cpu) {
     cpu.write_byte(wram::W_CUR_ENEMY_LVL, 5);
 
     // ld [wd11e], STARTER_PIKACHU
-    cpu.write_byte(wram::W_D11E, cpu.starter.into_index());
+    cpu.write_byte(wram::W_D11E, starter.into_index());
 
     // ld [wcf91], STARTER_PIKACHU
-    cpu.write_byte(wram::W_CF91, cpu.starter.into_index());
+    cpu.write_byte(wram::W_CF91, starter.into_index());
 
     // call AddPartyMon
     cpu.call(0x391c);
@@ -4,6 +4,7 @@ use crate::{
         constants::{battle_constants, input_constants},
         ram::wram,
     },
+    settings::settings,
 };
 
 pub fn pallet_town_script4(cpu: &mut Cpu) {
@@ -14,7 +15,7 @@ pub fn pallet_town_script4(cpu: &mut Cpu) {
     );
     cpu.write_byte(wram::W_LIST_SCROLL_OFFSET, 0);
     cpu.write_byte(wram::W_BATTLE_TYPE, battle_constants::BATTLE_TYPE_PIKACHU);
-    cpu.write_byte(wram::W_CUR_OPPONENT, cpu.starter.into_index());
+    cpu.write_byte(wram::W_CUR_OPPONENT, settings().starter.into_index());
     cpu.write_byte(wram::W_CUR_ENEMY_LVL, 5);
 
     // trigger the next script
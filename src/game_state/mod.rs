@@ -31,6 +31,10 @@ impl GameState {
         GameState { data }
     }
 
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
     pub fn byte(&self, addr: usize) -> u8 {
         self.data[addr]
     }
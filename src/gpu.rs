@@ -1,17 +1,39 @@
 use std::{
     cmp::Ordering,
-    sync::mpsc::{SendError, SyncSender},
+    sync::mpsc::{SyncSender, TrySendError},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    assets::atlases,
+    game::{self, ExitStatus},
+    hud::Hud,
+    pacing::{self, Pacer},
+    palette::Palette,
+    settings::settings,
 };
 
 const VRAM_SIZE: usize = 0x4000;
 const VOAM_SIZE: usize = 0xA0;
 
-const GB_SCALE: usize = 4;
-const GB_SCREEN_W: usize = 160;
-const GB_SCREEN_H: usize = 144;
+/// How often the screen is redrawn while paused, so that toasts still fade.
+const PAUSED_REDRAW: Duration = Duration::from_millis(33);
+
+pub const GB_SCREEN_W: usize = 160;
+pub const GB_SCREEN_H: usize = 144;
 
-pub const SCREEN_W: usize = GB_SCREEN_W * GB_SCALE;
-pub const SCREEN_H: usize = GB_SCREEN_H * GB_SCALE;
+/// Layers are positioned in pixels of the Game Boy screen scaled up this many
+/// times, so that a tile is 32×32, regardless of the output scale.
+const LAYER_SCALE: usize = 4;
+pub const LAYER_W: usize = GB_SCREEN_W * LAYER_SCALE;
+pub const LAYER_H: usize = GB_SCREEN_H * LAYER_SCALE;
+
+/// Size of the frames sent to the frontend when rendering at `scale` times
+/// the Game Boy resolution.
+pub const fn screen_size(scale: usize) -> (usize, usize) {
+    (GB_SCREEN_W * scale, GB_SCREEN_H * scale)
+}
 
 #[derive(PartialEq, Copy, Clone)]
 enum PrioType {
@@ -44,14 +66,112 @@ impl GpuTile {
     }
 }
 
+/// A region of an atlas drawn at any position on the screen. All positions
+/// and sizes are in screen pixels.
+#[derive(PartialEq, Copy, Clone)]
+pub struct GpuSprite {
+    pub atlas: GpuAtlas,
+    pub src_x: usize,
+    pub src_y: usize,
+    pub src_w: usize,
+    pub src_h: usize,
+    pub x: i32,
+    pub y: i32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub alpha: u8,
+    /// Objects with a higher z are drawn on top of those with a lower z.
+    pub z: i32,
+}
+
+impl GpuSprite {
+    pub const fn new(
+        atlas: GpuAtlas,
+        src_x: usize,
+        src_y: usize,
+        src_w: usize,
+        src_h: usize,
+        x: i32,
+        y: i32,
+    ) -> GpuSprite {
+        GpuSprite {
+            atlas,
+            src_x,
+            src_y,
+            src_w,
+            src_h,
+            x,
+            y,
+            flip_x: false,
+            flip_y: false,
+            alpha: 255,
+            z: 0,
+        }
+    }
+
+    /// A sprite showing `tile` with its top left corner at `x`, `y`.
+    pub const fn from_tile(tile: GpuTile, x: i32, y: i32) -> GpuSprite {
+        GpuSprite::new(tile.atlas, tile.src_x * 32, tile.src_y * 32, 32, 32, x, y)
+    }
+}
+
+/// A filled rectangle. All positions and sizes are in screen pixels.
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub struct GpuRect {
+    pub x: i32,
+    pub y: i32,
+    pub w: usize,
+    pub h: usize,
+    /// Red, green, blue and alpha.
+    pub color: [u8; 4],
+    /// Objects with a higher z are drawn on top of those with a lower z.
+    pub z: i32,
+}
+
+impl GpuRect {
+    pub const fn new(x: i32, y: i32, w: usize, h: usize, color: [u8; 4]) -> GpuRect {
+        GpuRect {
+            x,
+            y,
+            w,
+            h,
+            color,
+            z: 0,
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum GpuObject {
+    Sprite(GpuSprite),
+    Rect(GpuRect),
+}
+
+impl GpuObject {
+    fn z(&self) -> i32 {
+        match self {
+            GpuObject::Sprite(sprite) => sprite.z,
+            GpuObject::Rect(rect) => rect.z,
+        }
+    }
+}
+
+/// An overlay on top of the Game Boy screen. The background tiles are drawn
+/// first, followed by the objects ordered by their z value.
 pub struct GpuLayer {
     pub background: [Option<GpuTile>; 20 * 18],
+    pub objects: Vec<GpuObject>,
+    /// Opacity of the whole layer, applied on top of the opacity of every
+    /// tile and object.
+    pub opacity: u8,
 }
 
 impl GpuLayer {
     pub fn new() -> GpuLayer {
         GpuLayer {
             background: [None; 20 * 18],
+            objects: Vec::new(),
+            opacity: 255,
         }
     }
 
@@ -62,13 +182,55 @@ impl GpuLayer {
     pub fn clear_background(&mut self, x: usize, y: usize) {
         self.background[y * 20 + x] = None;
     }
+
+    /// Add a sprite and return its index in `objects`.
+    pub fn push_sprite(&mut self, sprite: GpuSprite) -> usize {
+        self.objects.push(GpuObject::Sprite(sprite));
+        self.objects.len() - 1
+    }
+
+    /// Add a rectangle and return its index in `objects`.
+    pub fn push_rect(&mut self, rect: GpuRect) -> usize {
+        self.objects.push(GpuObject::Rect(rect));
+        self.objects.len() - 1
+    }
+
+    pub fn sprite_mut(&mut self, idx: usize) -> Option<&mut GpuSprite> {
+        match self.objects.get_mut(idx) {
+            Some(GpuObject::Sprite(sprite)) => Some(sprite),
+            _ => None,
+        }
+    }
+
+    pub fn clear_objects(&mut self) {
+        self.objects.clear();
+    }
+}
+
+fn blend(dst: &mut [u8], src: &[u8], alpha: f32) {
+    let inv_alpha = 1.0 - alpha;
+
+    dst[0] = (dst[0] as f32 * inv_alpha + src[0] as f32 * alpha) as u8;
+    dst[1] = (dst[1] as f32 * inv_alpha + src[1] as f32 * alpha) as u8;
+    dst[2] = (dst[2] as f32 * inv_alpha + src[2] as f32 * alpha) as u8;
 }
 
-fn load_png(bytes: &[u8]) -> Vec<u8> {
-    image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
-        .unwrap()
-        .into_rgba8()
-        .into_raw()
+/// The part of a `w` × `h` area at `x`, `y` that is on a `max_w` × `max_h`
+/// screen, as ranges of offsets into the area.
+fn clip(
+    x: i32,
+    y: i32,
+    w: usize,
+    h: usize,
+    (max_w, max_h): (usize, usize),
+) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+    let clip_axis = |pos: i32, len: usize, max: usize| {
+        let start = (-pos).clamp(0, len as i32) as usize;
+        let end = (max as i32 - pos).clamp(start as i32, len as i32) as usize;
+        start..end
+    };
+
+    (clip_axis(x, w, max_w), clip_axis(y, h, max_h))
 }
 
 pub struct Gpu {
@@ -114,15 +276,21 @@ pub struct Gpu {
     pub interrupt: u8,
     hblanking: bool,
     update_screen: SyncSender<Vec<u8>>,
+    pacer: Pacer,
+    /// How many times the Game Boy resolution the frames are rendered at.
+    scale: usize,
+    colors: Vec<[u8; 3]>,
 
     layers: Vec<GpuLayer>,
-    atlas_box_border: Vec<u8>,
-    atlas_font: Vec<u8>,
-    atlas_pokemon_icons: Vec<u8>,
+    hud: Hud,
 }
 
 impl Gpu {
     pub fn new(update_screen: SyncSender<Vec<u8>>) -> Gpu {
+        // Load the asset pack at startup, instead of on the first frame that
+        // draws something from it.
+        atlases();
+
         Gpu {
             mode: 0,
             modeclock: 0,
@@ -166,18 +334,28 @@ impl Gpu {
             vrambank: 0,
             hblanking: false,
             update_screen,
+            pacer: Pacer::new(),
+            scale: settings().scale as usize,
+            colors: Palette::load(&settings().palette).lookup_table(),
             layers: vec![],
-            atlas_box_border: load_png(include_bytes!("../gfx/box_border.png")),
-            atlas_font: load_png(include_bytes!("../gfx/font.png")),
-            atlas_pokemon_icons: load_png(include_bytes!("../gfx/pokemon_icons.png")),
+            hud: Hud::new(),
         }
     }
 
+    pub fn set_palette(&mut self, palette: &Palette) {
+        self.colors = palette.lookup_table();
+    }
+
     pub fn layer_push(&mut self) -> usize {
         self.layers.push(GpuLayer::new());
         self.layers.len() - 1
     }
 
+    /// Whether any layers are being drawn, e.g. for a menu.
+    pub fn has_layers(&self) -> bool {
+        !self.layers.is_empty()
+    }
+
     pub fn layer_pop(&mut self, layer: usize) {
         self.layers.pop();
         assert_eq!(layer, self.layers.len());
@@ -188,6 +366,8 @@ impl Gpu {
     }
 
     pub fn do_cycle(&mut self, ticks: u32) {
+        self.pacer.add_cycles(ticks);
+
         if !self.lcd_on {
             return;
         }
@@ -253,6 +433,7 @@ impl Gpu {
                 self.wy_trigger = false;
                 self.interrupt |= 0x01;
                 self.update_screen();
+                self.wait_while_paused();
                 self.m1_inte
             }
             2 => self.m2_inte,
@@ -439,23 +620,30 @@ impl Gpu {
     }
 
     fn clear_screen(&mut self) {
-        for v in self.data.iter_mut() {
-            *v = 255;
+        let white = self.colors[0x7fff];
+        for v in self.data.chunks_exact_mut(3) {
+            v.copy_from_slice(&white);
         }
         self.update_screen();
     }
 
+    /// The Game Boy screen as last drawn by the game, without any layers.
+    pub fn frame(&self) -> &[u8] {
+        &self.data
+    }
+
     pub fn update_screen(&mut self) {
         debug_assert_eq!(self.data.len(), GB_SCREEN_W * GB_SCREEN_H * 3);
 
-        let mut screen = Vec::with_capacity(SCREEN_W * SCREEN_H * 3);
+        let (screen_w, screen_h) = screen_size(self.scale);
+        let mut screen = Vec::with_capacity(screen_w * screen_h * 3);
         let mut src = 0;
 
         for _ in 0..GB_SCREEN_H {
             let row_start = screen.len();
 
             for _ in 0..GB_SCREEN_W {
-                for _ in 0..GB_SCALE {
+                for _ in 0..self.scale {
                     screen.push(self.data[src]);
                     screen.push(self.data[src + 1]);
                     screen.push(self.data[src + 2]);
@@ -464,53 +652,133 @@ impl Gpu {
                 src += 3;
             }
 
-            for _ in 0..(GB_SCALE / 2) {
-                screen.extend_from_within(row_start..);
+            for _ in 1..self.scale {
+                screen.extend_from_within(row_start..row_start + screen_w * 3);
             }
         }
 
-        debug_assert_eq!(screen.len(), SCREEN_W * SCREEN_H * 3);
+        debug_assert_eq!(screen.len(), screen_w * screen_h * 3);
 
         for layer in &self.layers {
-            for (idx, tile) in layer.background.iter().enumerate() {
-                if let Some(tile) = tile {
-                    let dst_x = (idx % 20) * 32;
-                    let dst_y = (idx / 20) * 32;
-
-                    let (src, src_w) = match tile.atlas {
-                        GpuAtlas::BoxBorder => (&self.atlas_box_border[..], 96),
-                        GpuAtlas::Font => (&self.atlas_font[..], 512),
-                        GpuAtlas::PokemonIcons => (&self.atlas_pokemon_icons[..], 1024),
-                    };
-
-                    for dy in 0..32 {
-                        for dx in 0..32 {
-                            let src_idx =
-                                ((((tile.src_y * 32) + dy) * src_w) + (tile.src_x * 32) + dx) * 4;
-                            let dst_idx = (((dst_y + dy) * SCREEN_W) + dst_x + dx) * 3;
-
-                            let alpha = src[src_idx + 3] as f32 / 255.0;
-                            let inv_alpha = 1.0 - alpha;
-
-                            screen[dst_idx + 0] = (screen[dst_idx + 0] as f32 * inv_alpha
-                                + src[src_idx + 0] as f32 * alpha)
-                                as u8;
-                            screen[dst_idx + 1] = (screen[dst_idx + 1] as f32 * inv_alpha
-                                + src[src_idx + 1] as f32 * alpha)
-                                as u8;
-                            screen[dst_idx + 2] = (screen[dst_idx + 2] as f32 * inv_alpha
-                                + src[src_idx + 2] as f32 * alpha)
-                                as u8;
-                        }
-                    }
+            self.draw_layer(&mut screen, layer);
+        }
+
+        self.hud.update();
+        self.draw_layer(&mut screen, self.hud.layer());
+
+        // If the frontend hasn't shown the previous frame yet, this one is
+        // dropped instead of holding up the emulation.
+        match self.update_screen.try_send(screen) {
+            Ok(_) | Err(TrySendError::Full(_)) => {}
+            Err(TrySendError::Disconnected(_)) => game::shutdown(ExitStatus::Disconnected),
+        }
+    }
+
+    /// Keep showing the current frame, with the HUD on top, for as long as the
+    /// game is paused.
+    fn wait_while_paused(&mut self) {
+        while pacing::is_paused() {
+            thread::sleep(PAUSED_REDRAW);
+            self.update_screen();
+        }
+    }
+
+    /// Convert a position or size in layer pixels to screen pixels.
+    fn to_screen(&self, value: i32) -> i32 {
+        value * self.scale as i32 / LAYER_SCALE as i32
+    }
+
+    fn draw_layer(&self, screen: &mut [u8], layer: &GpuLayer) {
+        let opacity = layer.opacity as f32 / 255.0;
+
+        for (idx, tile) in layer.background.iter().enumerate() {
+            if let Some(tile) = tile {
+                let dst_x = (idx % 20) * 32;
+                let dst_y = (idx / 20) * 32;
+
+                let mut sprite = GpuSprite::from_tile(*tile, dst_x as i32, dst_y as i32);
+                sprite.alpha = layer.opacity;
+                self.draw_sprite(screen, &sprite);
+            }
+        }
+
+        let mut objects = layer.objects.iter().collect::<Vec<_>>();
+        objects.sort_by_key(|object| object.z());
+
+        for object in objects {
+            match object {
+                GpuObject::Sprite(sprite) => {
+                    let mut sprite = *sprite;
+                    sprite.alpha = (sprite.alpha as f32 * opacity) as u8;
+                    self.draw_sprite(screen, &sprite);
                 }
+                GpuObject::Rect(rect) => self.draw_rect(screen, rect, opacity),
             }
         }
+    }
+
+    fn draw_rect(&self, screen: &mut [u8], rect: &GpuRect, opacity: f32) {
+        let (screen_w, _) = screen_size(self.scale);
+        let alpha = rect.color[3] as f32 / 255.0 * opacity;
+
+        let x = self.to_screen(rect.x);
+        let y = self.to_screen(rect.y);
+        let w = (self.to_screen(rect.x + rect.w as i32) - x) as usize;
+        let h = (self.to_screen(rect.y + rect.h as i32) - y) as usize;
+        let (xs, ys) = clip(x, y, w, h, screen_size(self.scale));
+
+        for dy in ys {
+            for dx in xs.clone() {
+                let dst_x = (x + dx as i32) as usize;
+                let dst_y = (y + dy as i32) as usize;
+                let dst_idx = ((dst_y * screen_w) + dst_x) * 3;
+
+                blend(&mut screen[dst_idx..], &rect.color, alpha);
+            }
+        }
+    }
+
+    fn draw_sprite(&self, screen: &mut [u8], sprite: &GpuSprite) {
+        let atlas = atlases().get(sprite.atlas);
+
+        // A sprite reaching outside its atlas would repeat the edge pixels,
+        // e.g. from an asset pack with a smaller atlas than expected
+        let layer_tile_size = 8 * LAYER_SCALE;
+        if !atlas.contains(
+            sprite.src_x,
+            sprite.src_y,
+            sprite.src_w,
+            sprite.src_h,
+            layer_tile_size,
+        ) {
+            return;
+        }
+
+        let (screen_w, _) = screen_size(self.scale);
+        let tile_size = 8 * self.scale;
+
+        let x = self.to_screen(sprite.x);
+        let y = self.to_screen(sprite.y);
+        let w = (self.to_screen(sprite.x + sprite.src_w as i32) - x) as usize;
+        let h = (self.to_screen(sprite.y + sprite.src_h as i32) - y) as usize;
+        let src_x = self.to_screen(sprite.src_x as i32) as usize;
+        let src_y = self.to_screen(sprite.src_y as i32) as usize;
+
+        let opacity = sprite.alpha as f32 / 255.0;
+        let (xs, ys) = clip(x, y, w, h, screen_size(self.scale));
 
-        match self.update_screen.send(screen) {
-            Ok(_) => {}
-            Err(SendError(_)) => {
-                panic!("Screen disconnected")
+        for dy in ys {
+            for dx in xs.clone() {
+                let sx = if sprite.flip_x { w - 1 - dx } else { dx };
+                let sy = if sprite.flip_y { h - 1 - dy } else { dy };
+
+                let src = atlas.pixel(src_x + sx, src_y + sy, tile_size);
+                let dst_x = (x + dx as i32) as usize;
+                let dst_y = (y + dy as i32) as usize;
+                let dst_idx = ((dst_y * screen_w) + dst_x) * 3;
+
+                let alpha = src[3] as f32 / 255.0 * opacity;
+                blend(&mut screen[dst_idx..], src, alpha);
             }
         }
     }
@@ -534,32 +802,19 @@ impl Gpu {
 
     fn renderscan(&mut self) {
         for x in 0..GB_SCREEN_W {
-            self.setcolor(x, 255);
+            self.setrgb(x, 0x1f, 0x1f, 0x1f);
             self.bgprio[x] = PrioType::Normal;
         }
         self.draw_bg();
         self.draw_sprites();
     }
 
-    fn setcolor(&mut self, x: usize, color: u8) {
-        self.data[self.line as usize * GB_SCREEN_W * 3 + x * 3 + 0] = color;
-        self.data[self.line as usize * GB_SCREEN_W * 3 + x * 3 + 1] = color;
-        self.data[self.line as usize * GB_SCREEN_W * 3 + x * 3 + 2] = color;
-    }
-
     fn setrgb(&mut self, x: usize, r: u8, g: u8, b: u8) {
-        // Gameboy Color RGB correction
-        // Taken from the Gambatte emulator
         // assume r, g and b are between 0 and 1F
         let baseidx = self.line as usize * GB_SCREEN_W * 3 + x * 3;
+        let color = self.colors[r as usize | (g as usize) << 5 | (b as usize) << 10];
 
-        let r = r as u32;
-        let g = g as u32;
-        let b = b as u32;
-
-        self.data[baseidx + 0] = ((r * 13 + g * 2 + b) >> 1) as u8;
-        self.data[baseidx + 1] = ((g * 3 + b) << 1) as u8;
-        self.data[baseidx + 2] = ((r * 3 + g * 2 + b * 11) >> 1) as u8;
+        self.data[baseidx..baseidx + 3].copy_from_slice(&color);
     }
 
     fn draw_bg(&mut self) {
@@ -0,0 +1,130 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    game::home::text,
+    gpu::{GpuLayer, GpuRect, LAYER_H, LAYER_W},
+    pacing::{self, GB_FPS},
+    settings::settings,
+};
+
+const TOAST_DURATION: Duration = Duration::from_millis(2000);
+const TOAST_FADE_OUT: Duration = Duration::from_millis(500);
+const MAX_TOASTS: usize = 4;
+
+const CHAR_SIZE: i32 = 32;
+const PAUSED: &str = "PAUSED";
+const BORDER: i32 = 4;
+
+/// Toasts that have been requested but not yet picked up by the HUD. This is
+/// shared so that both the frontend and the game thread can show toasts.
+static PENDING_TOASTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Show `message` briefly at the bottom of the screen.
+pub fn show_toast(message: impl Into<String>) {
+    PENDING_TOASTS.lock().unwrap().push(message.into());
+}
+
+/// The overlay that is drawn on top of everything else, with the FPS counter
+/// and toasts.
+pub struct Hud {
+    layer: GpuLayer,
+    toasts: VecDeque<(String, Instant)>,
+    frames: u32,
+    frames_since: Instant,
+    fps: f32,
+}
+
+impl Hud {
+    pub fn new() -> Hud {
+        Hud {
+            layer: GpuLayer::new(),
+            toasts: VecDeque::new(),
+            frames: 0,
+            frames_since: Instant::now(),
+            fps: 0.0,
+        }
+    }
+
+    pub fn layer(&self) -> &GpuLayer {
+        &self.layer
+    }
+
+    /// Update the HUD, should be called once for every frame.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let paused = pacing::is_paused();
+
+        if !paused {
+            self.frames += 1;
+        }
+
+        let elapsed = now - self.frames_since;
+        if elapsed >= Duration::from_secs(1) {
+            self.fps = self.frames as f32 / elapsed.as_secs_f32();
+            self.frames = 0;
+            self.frames_since = now;
+        }
+
+        for message in PENDING_TOASTS.lock().unwrap().drain(..) {
+            self.toasts.push_back((message, now));
+        }
+
+        while self.toasts.len() > MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+
+        self.toasts
+            .retain(|(_, shown_at)| now - *shown_at < TOAST_DURATION);
+
+        self.layer.clear_objects();
+
+        if settings().show_fps {
+            let counters = format!("{:.0} FPS ×{:.1}", self.fps, self.fps / GB_FPS as f32);
+            let x = LAYER_W as i32 - (counters.chars().count() as i32) * CHAR_SIZE - BORDER;
+            self.draw_text(x, BORDER, &counters, 255);
+        }
+
+        if paused {
+            let x = (LAYER_W as i32 - (PAUSED.len() as i32) * CHAR_SIZE) / 2;
+            let y = (LAYER_H as i32 - CHAR_SIZE) / 2;
+            self.draw_text(x, y, PAUSED, 255);
+        }
+
+        let first_y = LAYER_H as i32 - (self.toasts.len() as i32) * (CHAR_SIZE + BORDER);
+
+        for idx in 0..self.toasts.len() {
+            let (message, shown_at) = self.toasts[idx].clone();
+            let remaining = TOAST_DURATION.saturating_sub(now - shown_at);
+            let alpha = if remaining < TOAST_FADE_OUT {
+                (255.0 * remaining.as_secs_f32() / TOAST_FADE_OUT.as_secs_f32()) as u8
+            } else {
+                255
+            };
+
+            let y = first_y + (idx as i32) * (CHAR_SIZE + BORDER);
+            self.draw_text(BORDER, y, &message, alpha);
+        }
+    }
+
+    fn draw_text(&mut self, x: i32, y: i32, message: &str, alpha: u8) {
+        // A dark border so that the text stands out from the game behind it.
+        let width = message.chars().count() * CHAR_SIZE as usize;
+        self.layer.push_rect(GpuRect::new(
+            x - BORDER,
+            y - BORDER,
+            width + 2 * BORDER as usize,
+            CHAR_SIZE as usize + 2 * BORDER as usize,
+            [0, 0, 0, alpha],
+        ));
+
+        for idx in text::place_string_at(&mut self.layer, x, y, message) {
+            if let Some(sprite) = self.layer.sprite_mut(idx) {
+                sprite.alpha = alpha;
+            }
+        }
+    }
+}
@@ -1,4 +1,63 @@
-use std::sync::mpsc::Receiver;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::Receiver,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::Action,
+    game::{self, ExitStatus},
+    settings::settings,
+};
+
+static TEXT_ENTRY: AtomicBool = AtomicBool::new(false);
+
+/// Whether a text entry screen has focus, in which case keys that type text
+/// shouldn't trigger the frontend's hotkeys.
+pub fn is_entering_text() -> bool {
+    TEXT_ENTRY.load(Ordering::Relaxed)
+}
+
+/// Marks a text entry screen as having focus until it is dropped.
+pub struct TextEntry(());
+
+impl TextEntry {
+    pub fn begin() -> TextEntry {
+        TEXT_ENTRY.store(true, Ordering::Relaxed);
+        TextEntry(())
+    }
+}
+
+impl Drop for TextEntry {
+    fn drop(&mut self) {
+        TEXT_ENTRY.store(false, Ordering::Relaxed);
+    }
+}
+
+static KEY_CAPTURE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the game is waiting for a key to bind, in which case no key should
+/// trigger the frontend's hotkeys.
+pub fn is_capturing_keys() -> bool {
+    KEY_CAPTURE.load(Ordering::Relaxed)
+}
+
+/// Marks the game as waiting for a key to bind until it is dropped.
+pub struct KeyCapture(());
+
+impl KeyCapture {
+    pub fn begin() -> KeyCapture {
+        KEY_CAPTURE.store(true, Ordering::Relaxed);
+        KeyCapture(())
+    }
+}
+
+impl Drop for KeyCapture {
+    fn drop(&mut self) {
+        KEY_CAPTURE.store(false, Ordering::Relaxed);
+    }
+}
 
 #[derive(Debug, Copy, Clone)]
 pub enum TextEvent {
@@ -8,7 +67,7 @@ pub enum TextEvent {
     Cancel,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum KeyboardKey {
     Escape,
     Left,
@@ -44,22 +103,98 @@ pub enum KeyboardKey {
     X,
     Y,
     Z,
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Tab,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadEnter,
+    Minus,
+    Equals,
+    LBracket,
+    RBracket,
+    Backslash,
+    Semicolon,
+    Apostrophe,
+    Grave,
+    Comma,
+    Period,
+    Slash,
+    LShift,
+    RShift,
+    LControl,
+    RControl,
+    LAlt,
+    RAlt,
 }
 
 impl KeyboardKey {
-    fn into_keypad_key(self) -> Option<KeypadKey> {
-        match self {
-            KeyboardKey::Right | KeyboardKey::D => Some(KeypadKey::Right),
-            KeyboardKey::Left | KeyboardKey::A => Some(KeypadKey::Left),
-            KeyboardKey::Up | KeyboardKey::W => Some(KeypadKey::Up),
-            KeyboardKey::Down | KeyboardKey::S => Some(KeypadKey::Down),
+    /// Short upper case name of the key, suitable for displaying in menus.
+    pub fn label(self) -> String {
+        let name = format!("{:?}", self);
 
-            KeyboardKey::Z | KeyboardKey::N => Some(KeypadKey::A),
-            KeyboardKey::X | KeyboardKey::M => Some(KeypadKey::B),
+        match name.strip_prefix("Key") {
+            Some(digit) if !digit.is_empty() => digit.to_owned(),
+            _ => name.to_uppercase(),
+        }
+    }
 
-            KeyboardKey::Return => Some(KeypadKey::Start),
-            KeyboardKey::Space => Some(KeypadKey::Select),
+    /// Whether the key types something on a text entry screen.
+    pub fn types_text(self) -> bool {
+        KeyboardEvent::Down {
+            key: self,
+            shift: false,
+        }
+        .into_text_event()
+        .is_some()
+    }
 
+    fn digit(self) -> Option<char> {
+        match self {
+            KeyboardKey::Key0 | KeyboardKey::Numpad0 => Some('0'),
+            KeyboardKey::Key1 | KeyboardKey::Numpad1 => Some('1'),
+            KeyboardKey::Key2 | KeyboardKey::Numpad2 => Some('2'),
+            KeyboardKey::Key3 | KeyboardKey::Numpad3 => Some('3'),
+            KeyboardKey::Key4 | KeyboardKey::Numpad4 => Some('4'),
+            KeyboardKey::Key5 | KeyboardKey::Numpad5 => Some('5'),
+            KeyboardKey::Key6 | KeyboardKey::Numpad6 => Some('6'),
+            KeyboardKey::Key7 | KeyboardKey::Numpad7 => Some('7'),
+            KeyboardKey::Key8 | KeyboardKey::Numpad8 => Some('8'),
+            KeyboardKey::Key9 | KeyboardKey::Numpad9 => Some('9'),
             _ => None,
         }
     }
@@ -67,26 +202,22 @@ impl KeyboardKey {
 
 #[derive(Debug, Copy, Clone)]
 pub enum KeyboardEvent {
-    Down { key: KeyboardKey, shift: bool },
-    Up { key: KeyboardKey },
+    Down {
+        key: KeyboardKey,
+        shift: bool,
+    },
+    Up {
+        key: KeyboardKey,
+    },
+    /// The player wants to quit, e.g. by closing the window.
+    Quit,
 }
 
 impl KeyboardEvent {
-    fn into_keypad_event(self) -> Option<KeypadEvent> {
-        match self {
-            KeyboardEvent::Down { key, .. } => key.into_keypad_key().map(KeypadEvent::Down),
-            KeyboardEvent::Up { key } => key.into_keypad_key().map(KeypadEvent::Up),
-        }
-    }
-
     #[rustfmt::skip]
     fn into_text_event(self) -> Option<TextEvent> {
         match self {
             KeyboardEvent::Down { key: KeyboardKey::Escape, .. } => Some(TextEvent::Cancel),
-            KeyboardEvent::Down { key: KeyboardKey::Left, .. } => None,
-            KeyboardEvent::Down { key: KeyboardKey::Up, .. } => None,
-            KeyboardEvent::Down { key: KeyboardKey::Right, .. } => None,
-            KeyboardEvent::Down { key: KeyboardKey::Down, .. } => None,
             KeyboardEvent::Down { key: KeyboardKey::Backspace, .. } => Some(TextEvent::Delete),
             KeyboardEvent::Down { key: KeyboardKey::Return, .. } => Some(TextEvent::Submit),
             KeyboardEvent::Down { key: KeyboardKey::Space, .. } => Some(TextEvent::Append(' ')),
@@ -116,8 +247,9 @@ impl KeyboardEvent {
             KeyboardEvent::Down { key: KeyboardKey::X, shift } => Some(TextEvent::Append(if shift { 'X' } else { 'x' })),
             KeyboardEvent::Down { key: KeyboardKey::Y, shift } => Some(TextEvent::Append(if shift { 'Y' } else { 'y' })),
             KeyboardEvent::Down { key: KeyboardKey::Z, shift } => Some(TextEvent::Append(if shift { 'Z' } else { 'z' })),
+            KeyboardEvent::Down { key, .. } => key.digit().map(TextEvent::Append),
 
-            KeyboardEvent::Up { .. } => None,
+            KeyboardEvent::Up { .. } | KeyboardEvent::Quit => None,
         }
     }
 }
@@ -127,8 +259,14 @@ pub struct Keypad {
     row1: u8,
     data: u8,
     events: Receiver<KeyboardEvent>,
+    hotkeys: Vec<Action>,
+    quit_requested: bool,
 }
 
+/// Returned instead of a key when the player asked to quit while waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuitRequested;
+
 #[derive(Copy, Clone)]
 pub enum KeypadKey {
     Right,
@@ -154,29 +292,95 @@ impl Keypad {
             row1: 0x0F,
             data: 0xFF,
             events,
+            hotkeys: Vec::new(),
+            quit_requested: false,
+        }
+    }
+
+    /// Hotkeys that have been pressed since the last call, in the order they
+    /// were pressed.
+    pub fn take_hotkeys(&mut self) -> Vec<Action> {
+        std::mem::take(&mut self.hotkeys)
+    }
+
+    /// Whether the player has asked to quit since the last call.
+    pub fn take_quit_request(&mut self) -> bool {
+        std::mem::take(&mut self.quit_requested)
+    }
+
+    /// Wait for the next event from the frontend. If the frontend has gone
+    /// away there is nobody left to play, so the game is stopped.
+    fn recv(&mut self) -> KeyboardEvent {
+        match self.events.recv() {
+            Ok(event) => event,
+            Err(_) => game::shutdown(ExitStatus::Disconnected),
+        }
+    }
+
+    fn keypad_event(&mut self, event: KeyboardEvent) -> Option<KeypadEvent> {
+        let settings = settings();
+        let bindings = &settings.key_bindings;
+
+        match event {
+            KeyboardEvent::Down { key, .. } => {
+                let action = bindings.action_for(key)?;
+
+                match action.keypad_key() {
+                    Some(key) => Some(KeypadEvent::Down(key)),
+                    None => {
+                        self.hotkeys.push(action);
+                        None
+                    }
+                }
+            }
+            KeyboardEvent::Up { key } => bindings
+                .action_for(key)
+                .and_then(Action::keypad_key)
+                .map(KeypadEvent::Up),
+            KeyboardEvent::Quit => {
+                self.quit_requested = true;
+                None
+            }
         }
     }
 
-    pub fn wait(&mut self) -> KeypadKey {
+    pub fn wait(&mut self) -> Result<KeypadKey, QuitRequested> {
         loop {
-            match self.events.recv().map(|e| e.into_keypad_event()) {
-                Ok(Some(KeypadEvent::Down(key))) => {
+            let event = self.recv();
+
+            match self.keypad_event(event) {
+                Some(KeypadEvent::Down(key)) => {
                     self.keydown(key);
-                    return key;
+                    return Ok(key);
                 }
-                Ok(Some(KeypadEvent::Up(key))) => self.keyup(key),
-                Ok(None) => {}
-                Err(_) => panic!("Keypad event channel closed"),
+                Some(KeypadEvent::Up(key)) => self.keyup(key),
+                None if self.take_quit_request() => return Err(QuitRequested),
+                None => {}
             }
         }
     }
 
-    pub fn text(&mut self) -> TextEvent {
+    /// Wait for any key to be pressed, without translating it through the key
+    /// bindings.
+    pub fn wait_keyboard(&mut self) -> Result<KeyboardKey, QuitRequested> {
         loop {
-            match self.events.recv().map(|e| e.into_text_event()) {
-                Ok(Some(event)) => return event,
-                Ok(None) => {}
-                Err(_) => panic!("Keypad event channel closed"),
+            match self.recv() {
+                KeyboardEvent::Down { key, .. } => return Ok(key),
+                KeyboardEvent::Up { .. } => {}
+                KeyboardEvent::Quit => return Err(QuitRequested),
+            }
+        }
+    }
+
+    pub fn text(&mut self) -> Result<TextEvent, QuitRequested> {
+        loop {
+            match self.recv() {
+                KeyboardEvent::Quit => return Err(QuitRequested),
+                event => {
+                    if let Some(event) = event.into_text_event() {
+                        return Ok(event);
+                    }
+                }
             }
         }
     }
@@ -192,7 +396,7 @@ impl Keypad {
 
     fn update(&mut self) {
         loop {
-            match self.events.try_recv().map(|e| e.into_keypad_event()) {
+            match self.events.try_recv().map(|e| self.keypad_event(e)) {
                 Ok(Some(KeypadEvent::Down(key))) => self.keydown(key),
                 Ok(Some(KeypadEvent::Up(key))) => self.keyup(key),
                 Ok(None) => {}
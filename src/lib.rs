@@ -1,21 +1,51 @@
 #![allow(clippy::bool_to_int_with_if, clippy::identity_op)]
 
-pub use crate::game::Game;
-pub use crate::gpu::{SCREEN_H, SCREEN_W};
-pub use crate::keypad::{KeyboardEvent, KeyboardKey};
+pub use crate::bindings::{Action, KeyBindings};
+pub use crate::crash::init_crash_reporting;
+pub use crate::game::{ExitStatus, Game};
+pub use crate::gpu::{screen_size, GB_SCREEN_H, GB_SCREEN_W};
+pub use crate::hud::show_toast;
+pub use crate::keypad::{is_capturing_keys, is_entering_text, KeyboardEvent, KeyboardKey};
+pub use crate::pacing::{
+    set_fast_forward, set_focused, speed_factor, toggle_pause, toggle_turbo, unpause,
+};
+pub use crate::palette::PaletteChoice;
+pub use crate::save_state::GrowthRate;
 pub use crate::save_state::PokemonSpecies;
+pub use crate::save_state::SaveDocument;
+pub use crate::save_state::{
+    Effectiveness, EncodeError, Move, MoveSlot, NameField, PokeString, PokemonType,
+    StatusCondition, TYPE_EFFECTS,
+};
+pub use crate::saves::{edit_save_json, export_save, export_save_json, import_save};
+pub use crate::screenshots::save_screenshot;
+pub use crate::settings::{
+    init_settings, settings, settings_mut, write_settings, AutosaveSettings, BattleStyle, Filter,
+    FocusLost, GameOptions, Overrides, Settings, TextSpeed,
+};
 
+mod assets;
+mod autosave;
+mod bindings;
+mod config;
 pub(crate) mod cpu;
+mod crash;
 pub(crate) mod game;
 mod game_state;
 mod gpu;
+mod hud;
 mod keypad;
 mod mbc5;
 mod mmu;
+mod pacing;
+mod palette;
 mod rom;
+mod save_metadata;
 mod save_state;
 mod saves;
+mod screenshots;
 mod serial;
+mod settings;
 mod sound;
 mod sound2;
 mod timer;
@@ -1,101 +1,160 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use glium::glutin::platform::run_return::EventLoopExtRunReturn;
-use rustic_yellow::{Game, KeyboardEvent, PokemonSpecies};
+use rustic_yellow::{Action, ExitStatus, Filter, Game, KeyboardEvent, KeyboardKey, Overrides};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver, SyncSender};
-use std::sync::{atomic::AtomicU64, Arc};
 use std::thread;
+use std::time::Duration;
+
+/// How long to wait for a new frame before handling window events again.
+const FRAME_POLL: Duration = Duration::from_millis(4);
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Which Pokemon to start with
-    #[arg(long, default_value = "Pikachu")]
-    starter: String,
+    #[command(flatten)]
+    overrides: Overrides,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage save files without starting the game
+    #[command(subcommand)]
+    Save(SaveCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum SaveCommand {
+    /// Add a save from another emulator or a cartridge dump
+    Import {
+        /// The .sav file to import
+        file: PathBuf,
+        /// Name of the new save, instead of the file name
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Write a save in a form that other emulators and cartridges load
+    Export {
+        /// Name of the save
+        name: String,
+        /// Where to write it, instead of <name>.sav in the current directory,
+        /// or standard output with --json
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Decode the save into JSON instead, with the party, boxes, items,
+        /// Pokédex and event flags
+        #[arg(long)]
+        json: bool,
+    },
+    /// Change a save to match a JSON document, in the form written by
+    /// `export --json`
+    Edit {
+        /// Name of the save
+        name: String,
+        /// The JSON document, or - to read it from standard input
+        file: PathBuf,
+    },
 }
 
 #[cfg(target_os = "windows")]
-fn create_window_builder() -> glium::glutin::window::WindowBuilder {
+fn create_window_builder((width, height): (usize, usize)) -> glium::glutin::window::WindowBuilder {
     use glium::glutin::platform::windows::WindowBuilderExtWindows;
     glium::glutin::window::WindowBuilder::new()
         .with_drag_and_drop(false)
         .with_inner_size(glium::glutin::dpi::LogicalSize::<u32>::from((
-            rustic_yellow::SCREEN_W as u32,
-            rustic_yellow::SCREEN_H as u32,
+            width as u32,
+            height as u32,
         )))
         .with_title("Rustic Yellow")
 }
 
 #[cfg(not(target_os = "windows"))]
-fn create_window_builder() -> glium::glutin::window::WindowBuilder {
+fn create_window_builder((width, height): (usize, usize)) -> glium::glutin::window::WindowBuilder {
     glium::glutin::window::WindowBuilder::new()
         .with_inner_size(glium::glutin::dpi::LogicalSize::<u32>::from((
-            rustic_yellow::SCREEN_W as u32,
-            rustic_yellow::SCREEN_H as u32,
+            width as u32,
+            height as u32,
         )))
         .with_title("Rustic Yellow")
 }
 
 fn main() {
-    env_logger::init();
+    rustic_yellow::init_crash_reporting();
 
     let args = Args::parse();
-    let starter: PokemonSpecies = args.starter.parse().unwrap();
+    rustic_yellow::init_settings(args.overrides);
 
-    let render_delay = Arc::new(AtomicU64::new(16_743));
+    if let Some(command) = args.command {
+        if let Err(error) = run_command(command) {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    let scale = rustic_yellow::settings().scale;
+    let frame_size = rustic_yellow::screen_size(scale as usize);
 
     let (sender1, receiver1) = mpsc::channel();
     let (sender2, receiver2) = mpsc::sync_channel(1);
 
     let mut eventloop = glium::glutin::event_loop::EventLoop::new();
-    let window_builder = create_window_builder();
-    let context_builder = glium::glutin::ContextBuilder::new();
+    let window_builder = create_window_builder(frame_size);
+    let context_builder =
+        glium::glutin::ContextBuilder::new().with_vsync(rustic_yellow::settings().vsync);
     let display =
         glium::backend::glutin::Display::new(window_builder, context_builder, &eventloop).unwrap();
-    set_window_size(display.gl_window().window());
+    set_window_size(display.gl_window().window(), scale);
 
     let mut texture = glium::texture::texture2d::Texture2d::empty_with_format(
         &display,
         glium::texture::UncompressedFloatFormat::U8U8U8,
         glium::texture::MipmapsOption::NoMipmap,
-        rustic_yellow::SCREEN_W as u32,
-        rustic_yellow::SCREEN_H as u32,
+        frame_size.0 as u32,
+        frame_size.1 as u32,
     )
     .unwrap();
 
-    let gamethread = thread::spawn(move || run_game(sender2, receiver1, starter));
+    let gamethread = thread::spawn(move || run_game(sender2, receiver1));
 
-    let periodic = timer_periodic(render_delay.clone());
+    let mut last_frame: Option<Vec<u8>> = None;
+    let mut quit_requested = false;
 
     #[rustfmt::skip]
     eventloop.run_return(move |ev, _evtarget, controlflow| {
         use glium::glutin::event::ElementState::{Pressed, Released};
-        use glium::glutin::event::VirtualKeyCode;
         use glium::glutin::event::{Event, KeyboardInput, WindowEvent};
 
         let mut stop = false;
         match ev {
             Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => stop = true,
+                WindowEvent::CloseRequested => {
+                    if quit_requested {
+                        // Closing again quits right away, without waiting for the game.
+                        stop = true;
+                    } else {
+                        quit_requested = true;
+                        rustic_yellow::unpause();
+                        let _ = sender1.send(KeyboardEvent::Quit);
+                    }
+                },
+                WindowEvent::Focused(focused) => rustic_yellow::set_focused(focused),
                 WindowEvent::KeyboardInput { input, .. } => match input {
-                    KeyboardInput { state: Pressed, virtual_keycode: Some(VirtualKeyCode::Key1), .. }
-                        => render_delay.store(16_743, std::sync::atomic::Ordering::Relaxed), // 59.7 fps
-                    KeyboardInput { state: Pressed, virtual_keycode: Some(VirtualKeyCode::Key2), .. }
-                        => render_delay.store(10_000, std::sync::atomic::Ordering::Relaxed), // 100 fps
-                    KeyboardInput { state: Pressed, virtual_keycode: Some(VirtualKeyCode::Key3), .. }
-                        => render_delay.store(8_333, std::sync::atomic::Ordering::Relaxed), // 120 fps
-                    KeyboardInput { state: Pressed, virtual_keycode: Some(VirtualKeyCode::Key4), .. }
-                        => render_delay.store(5_000, std::sync::atomic::Ordering::Relaxed), // 200 fps
-                    KeyboardInput { state: Pressed, virtual_keycode: Some(VirtualKeyCode::Key5), .. }
-                        => render_delay.store(4_166, std::sync::atomic::Ordering::Relaxed), // 240 fps
-                    KeyboardInput { state: Pressed, virtual_keycode: Some(VirtualKeyCode::Key6), .. }
-                        => render_delay.store(2_500, std::sync::atomic::Ordering::Relaxed), // 400 fps
                     KeyboardInput { state: Pressed, virtual_keycode: Some(glutinkey), modifiers, .. } => {
                         if let Some(key) = glutin_to_keyboard(glutinkey) {
+                            handle_hotkey(key, last_frame.as_deref(), frame_size);
                             let _ = sender1.send(KeyboardEvent::Down { key, shift: modifiers.shift() });
                         }
                     },
                     KeyboardInput { state: Released, virtual_keycode: Some(glutinkey), .. } => {
                         if let Some(key) = glutin_to_keyboard(glutinkey) {
+                            handle_hotkey_release(key);
                             let _ = sender1.send(KeyboardEvent::Up { key });
                         }
                     }
@@ -104,11 +163,14 @@ fn main() {
                 _ => (),
             },
             Event::MainEventsCleared => {
-                periodic.recv().unwrap();
-
-                match receiver2.try_recv() {
-                    Ok(data) => { recalculate_screen(&display, &mut texture, &data); },
-                    Err(mpsc::TryRecvError::Empty) => (),
+                // The game runs at its own pace and drops frames if they
+                // aren't picked up here in time, so just show the latest one.
+                match receiver2.recv_timeout(FRAME_POLL) {
+                    Ok(data) => {
+                        recalculate_screen(&display, &mut texture, &data);
+                        last_frame = Some(data);
+                    },
+                    Err(mpsc::RecvTimeoutError::Timeout) => (),
                     Err(..) => stop = true, // Remote end has hung-up
                 }
             }
@@ -119,53 +181,171 @@ fn main() {
         }
     });
 
-    let _ = gamethread.join();
+    match gamethread.join() {
+        Ok(status) => log::info!("Game stopped: {:?}", status),
+        Err(_) => log::error!("Game thread panicked"),
+    }
 }
 
-fn glutin_to_keyboard(
-    key: glium::glutin::event::VirtualKeyCode,
-) -> Option<rustic_yellow::KeyboardKey> {
+fn glutin_to_keyboard(key: glium::glutin::event::VirtualKeyCode) -> Option<KeyboardKey> {
     use glium::glutin::event::VirtualKeyCode;
     match key {
-        VirtualKeyCode::Escape => Some(rustic_yellow::KeyboardKey::Escape),
-        VirtualKeyCode::Left => Some(rustic_yellow::KeyboardKey::Left),
-        VirtualKeyCode::Up => Some(rustic_yellow::KeyboardKey::Up),
-        VirtualKeyCode::Right => Some(rustic_yellow::KeyboardKey::Right),
-        VirtualKeyCode::Down => Some(rustic_yellow::KeyboardKey::Down),
-        VirtualKeyCode::Back => Some(rustic_yellow::KeyboardKey::Backspace),
-        VirtualKeyCode::Return => Some(rustic_yellow::KeyboardKey::Return),
-        VirtualKeyCode::Space => Some(rustic_yellow::KeyboardKey::Space),
-        VirtualKeyCode::A => Some(rustic_yellow::KeyboardKey::A),
-        VirtualKeyCode::B => Some(rustic_yellow::KeyboardKey::B),
-        VirtualKeyCode::C => Some(rustic_yellow::KeyboardKey::C),
-        VirtualKeyCode::D => Some(rustic_yellow::KeyboardKey::D),
-        VirtualKeyCode::E => Some(rustic_yellow::KeyboardKey::E),
-        VirtualKeyCode::F => Some(rustic_yellow::KeyboardKey::F),
-        VirtualKeyCode::G => Some(rustic_yellow::KeyboardKey::G),
-        VirtualKeyCode::H => Some(rustic_yellow::KeyboardKey::H),
-        VirtualKeyCode::I => Some(rustic_yellow::KeyboardKey::I),
-        VirtualKeyCode::J => Some(rustic_yellow::KeyboardKey::J),
-        VirtualKeyCode::K => Some(rustic_yellow::KeyboardKey::K),
-        VirtualKeyCode::L => Some(rustic_yellow::KeyboardKey::L),
-        VirtualKeyCode::M => Some(rustic_yellow::KeyboardKey::M),
-        VirtualKeyCode::N => Some(rustic_yellow::KeyboardKey::N),
-        VirtualKeyCode::O => Some(rustic_yellow::KeyboardKey::O),
-        VirtualKeyCode::P => Some(rustic_yellow::KeyboardKey::P),
-        VirtualKeyCode::Q => Some(rustic_yellow::KeyboardKey::Q),
-        VirtualKeyCode::R => Some(rustic_yellow::KeyboardKey::R),
-        VirtualKeyCode::S => Some(rustic_yellow::KeyboardKey::S),
-        VirtualKeyCode::T => Some(rustic_yellow::KeyboardKey::T),
-        VirtualKeyCode::U => Some(rustic_yellow::KeyboardKey::U),
-        VirtualKeyCode::V => Some(rustic_yellow::KeyboardKey::V),
-        VirtualKeyCode::W => Some(rustic_yellow::KeyboardKey::W),
-        VirtualKeyCode::X => Some(rustic_yellow::KeyboardKey::X),
-        VirtualKeyCode::Y => Some(rustic_yellow::KeyboardKey::Y),
-        VirtualKeyCode::Z => Some(rustic_yellow::KeyboardKey::Z),
+        VirtualKeyCode::Escape => Some(KeyboardKey::Escape),
+        VirtualKeyCode::Left => Some(KeyboardKey::Left),
+        VirtualKeyCode::Up => Some(KeyboardKey::Up),
+        VirtualKeyCode::Right => Some(KeyboardKey::Right),
+        VirtualKeyCode::Down => Some(KeyboardKey::Down),
+        VirtualKeyCode::Back => Some(KeyboardKey::Backspace),
+        VirtualKeyCode::Return => Some(KeyboardKey::Return),
+        VirtualKeyCode::Space => Some(KeyboardKey::Space),
+        VirtualKeyCode::A => Some(KeyboardKey::A),
+        VirtualKeyCode::B => Some(KeyboardKey::B),
+        VirtualKeyCode::C => Some(KeyboardKey::C),
+        VirtualKeyCode::D => Some(KeyboardKey::D),
+        VirtualKeyCode::E => Some(KeyboardKey::E),
+        VirtualKeyCode::F => Some(KeyboardKey::F),
+        VirtualKeyCode::G => Some(KeyboardKey::G),
+        VirtualKeyCode::H => Some(KeyboardKey::H),
+        VirtualKeyCode::I => Some(KeyboardKey::I),
+        VirtualKeyCode::J => Some(KeyboardKey::J),
+        VirtualKeyCode::K => Some(KeyboardKey::K),
+        VirtualKeyCode::L => Some(KeyboardKey::L),
+        VirtualKeyCode::M => Some(KeyboardKey::M),
+        VirtualKeyCode::N => Some(KeyboardKey::N),
+        VirtualKeyCode::O => Some(KeyboardKey::O),
+        VirtualKeyCode::P => Some(KeyboardKey::P),
+        VirtualKeyCode::Q => Some(KeyboardKey::Q),
+        VirtualKeyCode::R => Some(KeyboardKey::R),
+        VirtualKeyCode::S => Some(KeyboardKey::S),
+        VirtualKeyCode::T => Some(KeyboardKey::T),
+        VirtualKeyCode::U => Some(KeyboardKey::U),
+        VirtualKeyCode::V => Some(KeyboardKey::V),
+        VirtualKeyCode::W => Some(KeyboardKey::W),
+        VirtualKeyCode::X => Some(KeyboardKey::X),
+        VirtualKeyCode::Y => Some(KeyboardKey::Y),
+        VirtualKeyCode::Z => Some(KeyboardKey::Z),
+        VirtualKeyCode::Key0 => Some(KeyboardKey::Key0),
+        VirtualKeyCode::Key1 => Some(KeyboardKey::Key1),
+        VirtualKeyCode::Key2 => Some(KeyboardKey::Key2),
+        VirtualKeyCode::Key3 => Some(KeyboardKey::Key3),
+        VirtualKeyCode::Key4 => Some(KeyboardKey::Key4),
+        VirtualKeyCode::Key5 => Some(KeyboardKey::Key5),
+        VirtualKeyCode::Key6 => Some(KeyboardKey::Key6),
+        VirtualKeyCode::Key7 => Some(KeyboardKey::Key7),
+        VirtualKeyCode::Key8 => Some(KeyboardKey::Key8),
+        VirtualKeyCode::Key9 => Some(KeyboardKey::Key9),
+        VirtualKeyCode::F1 => Some(KeyboardKey::F1),
+        VirtualKeyCode::F2 => Some(KeyboardKey::F2),
+        VirtualKeyCode::F3 => Some(KeyboardKey::F3),
+        VirtualKeyCode::F4 => Some(KeyboardKey::F4),
+        VirtualKeyCode::F5 => Some(KeyboardKey::F5),
+        VirtualKeyCode::F6 => Some(KeyboardKey::F6),
+        VirtualKeyCode::F7 => Some(KeyboardKey::F7),
+        VirtualKeyCode::F8 => Some(KeyboardKey::F8),
+        VirtualKeyCode::F9 => Some(KeyboardKey::F9),
+        VirtualKeyCode::F10 => Some(KeyboardKey::F10),
+        VirtualKeyCode::F11 => Some(KeyboardKey::F11),
+        VirtualKeyCode::F12 => Some(KeyboardKey::F12),
+        VirtualKeyCode::Tab => Some(KeyboardKey::Tab),
+        VirtualKeyCode::Insert => Some(KeyboardKey::Insert),
+        VirtualKeyCode::Delete => Some(KeyboardKey::Delete),
+        VirtualKeyCode::Home => Some(KeyboardKey::Home),
+        VirtualKeyCode::End => Some(KeyboardKey::End),
+        VirtualKeyCode::PageUp => Some(KeyboardKey::PageUp),
+        VirtualKeyCode::PageDown => Some(KeyboardKey::PageDown),
+        VirtualKeyCode::Numpad0 => Some(KeyboardKey::Numpad0),
+        VirtualKeyCode::Numpad1 => Some(KeyboardKey::Numpad1),
+        VirtualKeyCode::Numpad2 => Some(KeyboardKey::Numpad2),
+        VirtualKeyCode::Numpad3 => Some(KeyboardKey::Numpad3),
+        VirtualKeyCode::Numpad4 => Some(KeyboardKey::Numpad4),
+        VirtualKeyCode::Numpad5 => Some(KeyboardKey::Numpad5),
+        VirtualKeyCode::Numpad6 => Some(KeyboardKey::Numpad6),
+        VirtualKeyCode::Numpad7 => Some(KeyboardKey::Numpad7),
+        VirtualKeyCode::Numpad8 => Some(KeyboardKey::Numpad8),
+        VirtualKeyCode::Numpad9 => Some(KeyboardKey::Numpad9),
+        VirtualKeyCode::NumpadEnter => Some(KeyboardKey::NumpadEnter),
+        VirtualKeyCode::Minus => Some(KeyboardKey::Minus),
+        VirtualKeyCode::Equals => Some(KeyboardKey::Equals),
+        VirtualKeyCode::LBracket => Some(KeyboardKey::LBracket),
+        VirtualKeyCode::RBracket => Some(KeyboardKey::RBracket),
+        VirtualKeyCode::Backslash => Some(KeyboardKey::Backslash),
+        VirtualKeyCode::Semicolon => Some(KeyboardKey::Semicolon),
+        VirtualKeyCode::Apostrophe => Some(KeyboardKey::Apostrophe),
+        VirtualKeyCode::Grave => Some(KeyboardKey::Grave),
+        VirtualKeyCode::Comma => Some(KeyboardKey::Comma),
+        VirtualKeyCode::Period => Some(KeyboardKey::Period),
+        VirtualKeyCode::Slash => Some(KeyboardKey::Slash),
+        VirtualKeyCode::LShift => Some(KeyboardKey::LShift),
+        VirtualKeyCode::RShift => Some(KeyboardKey::RShift),
+        VirtualKeyCode::LControl => Some(KeyboardKey::LControl),
+        VirtualKeyCode::RControl => Some(KeyboardKey::RControl),
+        VirtualKeyCode::LAlt => Some(KeyboardKey::LAlt),
+        VirtualKeyCode::RAlt => Some(KeyboardKey::RAlt),
 
         _ => None,
     }
 }
 
+/// Handle the hotkeys that are implemented by the frontend. All other actions
+/// are handled by the game.
+fn handle_hotkey(key: KeyboardKey, last_frame: Option<&[u8]>, (width, height): (usize, usize)) {
+    if rustic_yellow::is_capturing_keys() {
+        return;
+    }
+
+    if key.types_text() && rustic_yellow::is_entering_text() {
+        return;
+    }
+
+    let action = rustic_yellow::settings().key_bindings.action_for(key);
+
+    match action {
+        Some(Action::Screenshot) => {
+            match last_frame.map(|frame| rustic_yellow::save_screenshot(frame, width, height)) {
+                Some(Ok(path)) => {
+                    log::info!("Saved screenshot to {}", path.display());
+                    rustic_yellow::show_toast("Screenshot saved");
+                }
+                Some(Err(error)) => {
+                    log::error!("Error saving screenshot: {}", error);
+                    rustic_yellow::show_toast("Screenshot failed");
+                }
+                None => {}
+            }
+        }
+
+        Some(Action::FastForward) => rustic_yellow::set_fast_forward(true),
+
+        Some(Action::Pause) => {
+            rustic_yellow::toggle_pause();
+        }
+
+        Some(Action::Turbo) => {
+            let turbo = rustic_yellow::toggle_turbo();
+            rustic_yellow::show_toast(if turbo { "Turbo on" } else { "Turbo off" });
+        }
+
+        Some(action) => {
+            if let Some(speed) = action.speed() {
+                let factor = rustic_yellow::speed_factor(speed);
+
+                rustic_yellow::settings_mut().speed = speed;
+                rustic_yellow::show_toast(format!("Speed ×{}", (factor * 10.0).round() / 10.0));
+            }
+        }
+
+        None => {}
+    }
+}
+
+fn handle_hotkey_release(key: KeyboardKey) {
+    let action = rustic_yellow::settings().key_bindings.action_for(key);
+
+    if action == Some(Action::FastForward) {
+        rustic_yellow::set_fast_forward(false);
+    }
+}
+
 fn recalculate_screen(
     display: &glium::Display,
     texture: &mut glium::texture::texture2d::Texture2d,
@@ -173,18 +353,19 @@ fn recalculate_screen(
 ) {
     use glium::Surface;
 
+    let (width, height) = (texture.width(), texture.height());
     let rawimage2d = glium::texture::RawImage2d {
         data: std::borrow::Cow::Borrowed(datavec),
-        width: rustic_yellow::SCREEN_W as u32,
-        height: rustic_yellow::SCREEN_H as u32,
+        width,
+        height,
         format: glium::texture::ClientFormat::U8U8U8,
     };
     texture.write(
         glium::Rect {
             left: 0,
             bottom: 0,
-            width: rustic_yellow::SCREEN_W as u32,
-            height: rustic_yellow::SCREEN_H as u32,
+            width,
+            height,
         },
         rawimage2d,
     );
@@ -200,39 +381,83 @@ fn recalculate_screen(
             width: target_w as i32,
             height: -(target_h as i32),
         },
-        glium::uniforms::MagnifySamplerFilter::Nearest,
+        match rustic_yellow::settings().filter {
+            Filter::Nearest => glium::uniforms::MagnifySamplerFilter::Nearest,
+            Filter::Linear => glium::uniforms::MagnifySamplerFilter::Linear,
+        },
     );
     target.finish().unwrap();
 }
 
-fn run_game(
-    sender: SyncSender<Vec<u8>>,
-    receiver: Receiver<KeyboardEvent>,
-    starter: PokemonSpecies,
-) {
-    Game::new(sender, receiver, starter).boot();
-}
+fn run_command(command: Command) -> io::Result<()> {
+    match command {
+        Command::Save(SaveCommand::Import { file, name }) => {
+            let name = match name {
+                Some(name) => name,
+                None => file
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            };
 
-fn timer_periodic(delay: Arc<AtomicU64>) -> Receiver<()> {
-    let (tx, rx) = std::sync::mpsc::sync_channel(1);
-    std::thread::spawn(move || loop {
-        let micros = delay.load(std::sync::atomic::Ordering::Relaxed);
-        std::thread::sleep(std::time::Duration::from_micros(micros));
-        if tx.send(()).is_err() {
-            break;
+            let path = rustic_yellow::import_save(&file, &name)?;
+            println!("Imported {} as {}", file.display(), path.display());
         }
-    });
-    rx
+
+        Command::Save(SaveCommand::Export {
+            name,
+            output,
+            json: true,
+        }) => {
+            let json = rustic_yellow::export_save_json(&name)?;
+
+            match output {
+                Some(output) => {
+                    fs::write(&output, json)?;
+                    println!("Exported {} to {}", name, output.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+
+        Command::Save(SaveCommand::Export {
+            name,
+            output,
+            json: false,
+        }) => {
+            let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.sav", name)));
+
+            rustic_yellow::export_save(&name, &output)?;
+            println!("Exported {} to {}", name, output.display());
+        }
+
+        Command::Save(SaveCommand::Edit { name, file }) => {
+            let json = if file.as_os_str() == "-" {
+                io::read_to_string(io::stdin())?
+            } else {
+                fs::read_to_string(&file)?
+            };
+
+            rustic_yellow::edit_save_json(&name, &json)?;
+            println!("Edited {}", name);
+        }
+    }
+
+    Ok(())
+}
+
+fn run_game(sender: SyncSender<Vec<u8>>, receiver: Receiver<KeyboardEvent>) -> ExitStatus {
+    Game::new(sender, receiver).boot()
 }
 
-fn set_window_size(window: &glium::glutin::window::Window) {
+fn set_window_size(window: &glium::glutin::window::Window, scale: u8) {
     use glium::glutin::dpi::{LogicalSize, PhysicalSize};
 
     let dpi = window.scale_factor();
 
     let physical_size = PhysicalSize::<u32>::from((
-        rustic_yellow::SCREEN_W as u32,
-        rustic_yellow::SCREEN_H as u32,
+        rustic_yellow::GB_SCREEN_W as u32 * scale as u32,
+        rustic_yellow::GB_SCREEN_H as u32 * scale as u32,
     ));
     let logical_size = LogicalSize::<u32>::from_physical(physical_size, dpi);
 
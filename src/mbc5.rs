@@ -1,6 +1,6 @@
-use std::path;
+use std::{io, path};
 
-use crate::{rom::ROM, save_state::SaveState};
+use crate::{rom::ROM, save_state::SaveState, saves, settings::settings};
 
 pub struct MBC5 {
     ram: SaveState,
@@ -37,10 +37,26 @@ impl MBC5 {
         self.save_path = Some(save_path);
     }
 
-    pub fn save_to_disk(&mut self) {
+    pub fn save_path(&self) -> Option<&path::Path> {
+        self.save_path.as_deref()
+    }
+
+    /// Write SRAM to the save file, with its checksums updated, after moving
+    /// the previous version into the backups.
+    pub fn save_to_disk(&mut self) -> io::Result<()> {
         if let Some(ref save_path) = self.save_path {
-            self.ram.write_to_file(save_path).unwrap();
+            // Rust code, e.g. Bill's PC, changes SRAM without
+            // keeping the game's checksums up to date
+            self.ram.update_checksums();
+
+            if let Err(e) = saves::rotate_backups(save_path, settings().save_backups) {
+                log::error!("Error backing up {}: {}", save_path.display(), e);
+            }
+
+            self.ram.write_to_file(save_path)?;
         }
+
+        Ok(())
     }
 
     pub fn readrom(&self, a: u16) -> u8 {
@@ -0,0 +1,153 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::settings::{settings, FocusLost};
+
+/// Clock speed of the Game Boy, in the ticks that the GPU counts.
+const CLOCK_SPEED: u32 = 4_194_304;
+
+/// Ticks from the start of one frame to the start of the next.
+const CYCLES_PER_FRAME: u32 = 70_224;
+
+/// Frames per second of the original hardware, about 59.73.
+pub const GB_FPS: f64 = CLOCK_SPEED as f64 / CYCLES_PER_FRAME as f64;
+
+/// If the emulation falls further behind than this, e.g. because it was
+/// waiting for input in a menu, it continues from the current time instead of
+/// running fast to catch up.
+const MAX_LAG: Duration = Duration::from_millis(100);
+
+static FAST_FORWARD: AtomicBool = AtomicBool::new(false);
+static TURBO: AtomicBool = AtomicBool::new(false);
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+static FOCUS_LOST: AtomicBool = AtomicBool::new(false);
+
+/// Toggle the pause hotkey, returning whether the game is now paused.
+pub fn toggle_pause() -> bool {
+    !PAUSED.fetch_xor(true, Ordering::Relaxed)
+}
+
+/// Undo the pause hotkey, e.g. so that the game can react to quitting.
+pub fn unpause() {
+    PAUSED.store(false, Ordering::Relaxed);
+}
+
+/// Tell the game whether the window has focus, which pauses or mutes it
+/// depending on the settings.
+pub fn set_focused(focused: bool) {
+    FOCUS_LOST.store(!focused, Ordering::Relaxed);
+
+    // The window won't hear about the fast forward key being released
+    if !focused {
+        set_fast_forward(false);
+    }
+}
+
+/// Whether the emulation should stay at the current frame.
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+        || (FOCUS_LOST.load(Ordering::Relaxed) && settings().on_focus_lost == FocusLost::Pause)
+}
+
+/// Whether music and sound effects should play at no volume, because the
+/// window doesn't have focus. Unlike pausing, the game keeps running.
+pub fn is_muted_by_focus() -> bool {
+    FOCUS_LOST.load(Ordering::Relaxed) && settings().on_focus_lost == FocusLost::Mute
+}
+
+/// Run at the fast forward speed for as long as `held` is true.
+pub fn set_fast_forward(held: bool) {
+    FAST_FORWARD.store(held, Ordering::Relaxed);
+}
+
+/// Toggle running as fast as possible, returning whether it's now on.
+pub fn toggle_turbo() -> bool {
+    !TURBO.fetch_xor(true, Ordering::Relaxed)
+}
+
+/// How many times faster than the original hardware a speed preset runs. The
+/// presets are 59.7, 100, 120, 200, 240 and 400 frames per second.
+pub fn speed_factor(speed: u8) -> f64 {
+    // Microseconds per frame
+    let frame_time = match speed {
+        1 => 16_743,
+        2 => 10_000,
+        3 => 8_333,
+        4 => 5_000,
+        5 => 4_166,
+        _ => 2_500,
+    };
+
+    16_743.0 / frame_time as f64
+}
+
+/// How many times faster than the original hardware to run right now, or
+/// `None` to run as fast as possible.
+fn current_factor() -> Option<f64> {
+    if TURBO.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let settings = settings();
+
+    if FAST_FORWARD.load(Ordering::Relaxed) {
+        return match settings.fast_forward_speed {
+            0 => None,
+            speed => Some(speed as f64),
+        };
+    }
+
+    Some(speed_factor(settings.speed))
+}
+
+/// Keeps the emulation running at the right speed, by sleeping whenever it
+/// gets ahead of the wall clock. Time is measured in emulated cycles, so the
+/// pace doesn't depend on how often frames are shown.
+pub struct Pacer {
+    cycles: u32,
+    next_frame: Instant,
+}
+
+impl Pacer {
+    pub fn new() -> Pacer {
+        Pacer {
+            cycles: 0,
+            next_frame: Instant::now(),
+        }
+    }
+
+    /// Account for `ticks` emulated GPU ticks, waiting if a whole frame has
+    /// been emulated ahead of time.
+    pub fn add_cycles(&mut self, ticks: u32) {
+        self.cycles += ticks;
+
+        while self.cycles >= CYCLES_PER_FRAME {
+            self.cycles -= CYCLES_PER_FRAME;
+            self.wait_for_frame();
+        }
+    }
+
+    fn wait_for_frame(&mut self) {
+        let now = Instant::now();
+
+        let factor = match current_factor() {
+            Some(factor) => factor,
+            None => {
+                self.next_frame = now;
+                return;
+            }
+        };
+
+        self.next_frame += Duration::from_secs_f64(1.0 / (GB_FPS * factor));
+
+        if self.next_frame > now {
+            thread::sleep(self.next_frame - now);
+        } else if now - self.next_frame > MAX_LAG {
+            self.next_frame = now;
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::{convert::Infallible, fmt, fs, io, path::PathBuf, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+const PALETTES_DIR: &str = "palettes";
+
+/// Which palette to use for the colours of the Game Boy screen.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum PaletteChoice {
+    /// Game Boy Color colours, corrected to look like they did on the screen.
+    #[default]
+    Gbc,
+    /// Game Boy Color colours, as they are stored in the palette registers.
+    GbcRaw,
+    DmgGreen,
+    PocketGrey,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+    /// A palette read from `palettes/<name>.toml` in the config directory.
+    Custom(String),
+}
+
+impl PaletteChoice {
+    pub const PRESETS: [PaletteChoice; 7] = [
+        PaletteChoice::Gbc,
+        PaletteChoice::GbcRaw,
+        PaletteChoice::DmgGreen,
+        PaletteChoice::PocketGrey,
+        PaletteChoice::Protanopia,
+        PaletteChoice::Deuteranopia,
+        PaletteChoice::Tritanopia,
+    ];
+
+    /// All presets followed by the custom palettes in the config directory.
+    pub fn all() -> Vec<PaletteChoice> {
+        let mut result = PaletteChoice::PRESETS.to_vec();
+
+        match list_custom_palettes() {
+            Ok(names) => result.extend(names.into_iter().map(PaletteChoice::Custom)),
+            Err(e) => log::error!("Error listing custom palettes: {}", e),
+        }
+
+        result
+    }
+
+    /// The palette after this one in [`PaletteChoice::all`].
+    pub fn next(&self) -> PaletteChoice {
+        let all = PaletteChoice::all();
+        let idx = all.iter().position(|p| p == self).map_or(0, |idx| idx + 1);
+
+        all.get(idx).unwrap_or(&all[0]).clone()
+    }
+}
+
+impl fmt::Display for PaletteChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteChoice::Gbc => write!(f, "gbc"),
+            PaletteChoice::GbcRaw => write!(f, "gbc_raw"),
+            PaletteChoice::DmgGreen => write!(f, "dmg_green"),
+            PaletteChoice::PocketGrey => write!(f, "pocket_grey"),
+            PaletteChoice::Protanopia => write!(f, "protanopia"),
+            PaletteChoice::Deuteranopia => write!(f, "deuteranopia"),
+            PaletteChoice::Tritanopia => write!(f, "tritanopia"),
+            PaletteChoice::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl FromStr for PaletteChoice {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(PaletteChoice::PRESETS
+            .into_iter()
+            .find(|p| p.to_string() == s)
+            .unwrap_or_else(|| PaletteChoice::Custom(s.to_string())))
+    }
+}
+
+impl From<String> for PaletteChoice {
+    fn from(s: String) -> Self {
+        match s.parse() {
+            Ok(choice) => choice,
+            Err(e) => match e {},
+        }
+    }
+}
+
+impl From<PaletteChoice> for String {
+    fn from(choice: PaletteChoice) -> Self {
+        choice.to_string()
+    }
+}
+
+type Matrix = [[f32; 3]; 3];
+
+// Colour blindness simulation matrices from Machado, Oliveira & Fernandes
+// (2009), at full severity.
+const PROTANOPIA: Matrix = [
+    [0.152286, 1.052583, -0.204868],
+    [0.114503, 0.786281, 0.099216],
+    [-0.003882, -0.048116, 1.051998],
+];
+const DEUTERANOPIA: Matrix = [
+    [0.367322, 0.860646, -0.227968],
+    [0.280085, 0.672501, 0.047413],
+    [-0.011820, 0.042940, 0.968881],
+];
+const TRITANOPIA: Matrix = [
+    [1.255528, -0.076749, -0.178779],
+    [-0.078411, 0.930809, 0.147602],
+    [0.004733, 0.691367, 0.303900],
+];
+
+// Where the information that is lost is moved to, for red-green and
+// blue-yellow colour blindness respectively.
+const SHIFT_RED_GREEN: Matrix = [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]];
+const SHIFT_BLUE_YELLOW: Matrix = [[1.0, 0.0, 0.7], [0.0, 1.0, 0.7], [0.0, 0.0, 0.0]];
+
+fn daltonize(simulation: &Matrix, shift: &Matrix) -> Matrix {
+    // corrected = color + shift * (color - simulation * color)
+    let mut result = [[0.0; 3]; 3];
+
+    for (row, result_row) in result.iter_mut().enumerate() {
+        for (col, value) in result_row.iter_mut().enumerate() {
+            let identity = if row == col { 1.0 } else { 0.0 };
+            let error = (0..3)
+                .map(|k| {
+                    let identity = if k == col { 1.0 } else { 0.0 };
+                    shift[row][k] * (identity - simulation[k][col])
+                })
+                .sum::<f32>();
+
+            *value = identity + error;
+        }
+    }
+
+    result
+}
+
+#[derive(Deserialize)]
+struct CustomPalette {
+    shades: [String; 4],
+}
+
+fn parse_hex_color(s: &str) -> Option<[u8; 3]> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+
+    if s.len() != 6 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let value = u32::from_str_radix(s, 16).ok()?;
+    Some([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+/// Whether `name` can be used as a file name in the palettes directory,
+/// without reaching outside of it.
+fn is_valid_palette_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['.', '/', '\\', ':'])
+}
+
+fn get_custom_palette_path(name: &str) -> io::Result<PathBuf> {
+    if !is_valid_palette_name(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid palette name \"{}\"", name),
+        ));
+    }
+
+    Ok(config::get_config_path(PALETTES_DIR)?
+        .join(name)
+        .with_extension("toml"))
+}
+
+fn list_custom_palettes() -> io::Result<Vec<String>> {
+    let dir = match fs::read_dir(config::get_config_path(PALETTES_DIR)?) {
+        Ok(dir) => dir,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut names = Vec::new();
+
+    for entry in dir {
+        let path = entry?.path();
+
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            match path.file_stem().and_then(|s| s.to_str()) {
+                Some(stem) if is_valid_palette_name(stem) => names.push(stem.to_string()),
+                _ => log::warn!("Ignoring palette with invalid name: {}", path.display()),
+            }
+        }
+    }
+
+    names.sort();
+    Ok(names)
+}
+
+fn load_custom_palette(name: &str) -> io::Result<[[u8; 3]; 4]> {
+    let text = fs::read_to_string(get_custom_palette_path(name)?)?;
+    let palette: CustomPalette =
+        toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let mut shades = [[0; 3]; 4];
+
+    for (shade, s) in shades.iter_mut().zip(&palette.shades) {
+        *shade = parse_hex_color(s).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid colour \"{}\", expected #rrggbb", s),
+            )
+        })?;
+    }
+
+    Ok(shades)
+}
+
+/// Converts the 15-bit colours of the Game Boy Color to 24-bit colours for
+/// the screen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Palette {
+    Raw,
+    Corrected,
+    /// Four shades from lightest to darkest, picked by the brightness of the
+    /// original colour.
+    Shades([[u8; 3]; 4]),
+    /// The corrected colours transformed by a matrix.
+    Matrix(Matrix),
+}
+
+impl Palette {
+    pub fn load(choice: &PaletteChoice) -> Palette {
+        match choice {
+            PaletteChoice::Gbc => Palette::Corrected,
+            PaletteChoice::GbcRaw => Palette::Raw,
+            PaletteChoice::DmgGreen => Palette::Shades([
+                [0x9b, 0xbc, 0x0f],
+                [0x8b, 0xac, 0x0f],
+                [0x30, 0x62, 0x30],
+                [0x0f, 0x38, 0x0f],
+            ]),
+            PaletteChoice::PocketGrey => Palette::Shades([
+                [0xc5, 0xca, 0xa4],
+                [0x8c, 0x92, 0x6b],
+                [0x4a, 0x51, 0x38],
+                [0x18, 0x18, 0x18],
+            ]),
+            PaletteChoice::Protanopia => Palette::Matrix(daltonize(&PROTANOPIA, &SHIFT_RED_GREEN)),
+            PaletteChoice::Deuteranopia => {
+                Palette::Matrix(daltonize(&DEUTERANOPIA, &SHIFT_RED_GREEN))
+            }
+            PaletteChoice::Tritanopia => {
+                Palette::Matrix(daltonize(&TRITANOPIA, &SHIFT_BLUE_YELLOW))
+            }
+            PaletteChoice::Custom(name) => match load_custom_palette(name) {
+                Ok(shades) => Palette::Shades(shades),
+                Err(e) => {
+                    log::error!("Error loading palette \"{}\": {}", name, e);
+                    Palette::Corrected
+                }
+            },
+        }
+    }
+
+    /// Convert a colour where `r`, `g` and `b` are between 0 and 0x1F.
+    pub fn color(&self, r: u8, g: u8, b: u8) -> [u8; 3] {
+        let (r, g, b) = (r as u32, g as u32, b as u32);
+
+        match self {
+            Palette::Raw => [
+                ((r << 3) | (r >> 2)) as u8,
+                ((g << 3) | (g >> 2)) as u8,
+                ((b << 3) | (b >> 2)) as u8,
+            ],
+
+            // Gameboy Color RGB correction
+            // Taken from the Gambatte emulator
+            Palette::Corrected => [
+                ((r * 13 + g * 2 + b) >> 1) as u8,
+                ((g * 3 + b) << 1) as u8,
+                ((r * 3 + g * 2 + b * 11) >> 1) as u8,
+            ],
+
+            Palette::Shades(shades) => {
+                let luma = (r * 299 + g * 587 + b * 114) as f32 / (31.0 * 1000.0);
+                shades[(((1.0 - luma) * 4.0) as usize).min(3)]
+            }
+
+            Palette::Matrix(matrix) => {
+                let color = Palette::Corrected.color(r as u8, g as u8, b as u8);
+                let mut result = [0; 3];
+
+                for (row, value) in result.iter_mut().enumerate() {
+                    let sum = (0..3)
+                        .map(|col| matrix[row][col] * color[col] as f32)
+                        .sum::<f32>();
+
+                    *value = sum.round().clamp(0.0, 255.0) as u8;
+                }
+
+                result
+            }
+        }
+    }
+
+    /// Every colour converted up front, indexed by `r | g << 5 | b << 10`.
+    pub fn lookup_table(&self) -> Vec<[u8; 3]> {
+        (0..0x8000u16)
+            .map(|c| {
+                self.color(
+                    (c & 0x1f) as u8,
+                    ((c >> 5) & 0x1f) as u8,
+                    ((c >> 10) & 0x1f) as u8,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#e0f8d0"), Some([0xe0, 0xf8, 0xd0]));
+        assert_eq!(parse_hex_color("081820"), Some([0x08, 0x18, 0x20]));
+        assert_eq!(parse_hex_color("#88C070"), Some([0x88, 0xc0, 0x70]));
+
+        assert_eq!(parse_hex_color(""), None);
+        assert_eq!(parse_hex_color("#fff"), None);
+        assert_eq!(parse_hex_color("#e0f8d0ff"), None);
+        assert_eq!(parse_hex_color("#e0f8dg"), None);
+        assert_eq!(parse_hex_color("+12345"), None);
+        assert_eq!(parse_hex_color("##12345"), None);
+    }
+
+    #[test]
+    fn test_palette_choice_string() {
+        for choice in PaletteChoice::PRESETS {
+            assert_eq!(choice.to_string().parse(), Ok(choice));
+        }
+
+        assert_eq!("gbc_raw".parse(), Ok(PaletteChoice::GbcRaw));
+        assert_eq!(
+            "mine".parse(),
+            Ok(PaletteChoice::Custom("mine".to_string()))
+        );
+        assert_eq!(
+            PaletteChoice::Custom("mine".to_string()).to_string(),
+            "mine"
+        );
+    }
+
+    #[test]
+    fn test_palette_choice_toml() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Settings {
+            palette: PaletteChoice,
+        }
+
+        let settings: Settings = toml::from_str("palette = \"dmg_green\"").unwrap();
+        assert_eq!(settings.palette, PaletteChoice::DmgGreen);
+
+        for palette in [
+            PaletteChoice::PocketGrey,
+            PaletteChoice::Custom("mine".to_string()),
+        ] {
+            let settings = Settings { palette };
+            let text = toml::to_string(&settings).unwrap();
+            assert_eq!(toml::from_str::<Settings>(&text).unwrap(), settings);
+        }
+    }
+
+    #[test]
+    fn test_next_preset() {
+        assert_eq!(PaletteChoice::Gbc.next(), PaletteChoice::GbcRaw);
+        assert_eq!(PaletteChoice::DmgGreen.next(), PaletteChoice::PocketGrey);
+    }
+
+    #[test]
+    fn test_daltonize() {
+        let protanopia = Palette::load(&PaletteChoice::Protanopia);
+        let deuteranopia = Palette::load(&PaletteChoice::Deuteranopia);
+        let tritanopia = Palette::load(&PaletteChoice::Tritanopia);
+
+        assert_eq!(protanopia.color(31, 0, 0), [201, 98, 170]);
+        assert_eq!(deuteranopia.color(0, 31, 0), [31, 143, 0]);
+        assert_eq!(tritanopia.color(0, 0, 31), [99, 95, 170]);
+
+        // Greys look the same to everyone, so they are left alone
+        for palette in [protanopia, deuteranopia, tritanopia] {
+            assert_eq!(palette.color(16, 16, 16), [128, 128, 128]);
+        }
+    }
+
+    #[test]
+    fn test_palette_name() {
+        assert!(is_valid_palette_name("gameboy_pocket"));
+
+        assert!(!is_valid_palette_name(""));
+        assert!(!is_valid_palette_name("../../foo"));
+        assert!(!is_valid_palette_name("foo/bar"));
+        assert!(!is_valid_palette_name("foo\\bar"));
+        assert!(!is_valid_palette_name("foo.toml"));
+        assert!(get_custom_palette_path("../../foo").is_err());
+    }
+}
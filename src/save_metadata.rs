@@ -0,0 +1,191 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::constants::map_constants,
+    gpu::{GB_SCREEN_H, GB_SCREEN_W},
+    save_state::{PokemonSpecies, SaveState},
+    screenshots,
+    settings::{settings, species_name},
+};
+
+/// What the save list shows about a save, kept in `name.toml` next to
+/// `name.sav` and updated every time the save is written.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveMetadata {
+    /// When the save was first written, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub created: u64,
+    /// When the save was last written, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub last_played: u64,
+    /// The starter the game was started with. Unknown for saves from before
+    /// metadata was kept, and for imported saves.
+    #[serde(
+        default,
+        with = "species_name::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub starter: Option<PokemonSpecies>,
+    /// The town or route the player saved in, or the one they entered the
+    /// current building or cave from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub party: Vec<PartyMember>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartyMember {
+    #[serde(with = "species_name")]
+    pub species: PokemonSpecies,
+    pub level: u8,
+}
+
+pub fn get_metadata_path(save_path: &Path) -> PathBuf {
+    save_path.with_extension("toml")
+}
+
+/// A screenshot of the game when it was last saved.
+pub fn get_thumbnail_path(save_path: &Path) -> PathBuf {
+    save_path.with_extension("png")
+}
+
+impl SaveMetadata {
+    /// The metadata that can be read from the save itself, e.g. for saves
+    /// that were imported or written before metadata was kept.
+    pub fn from_save(save: &SaveState) -> SaveMetadata {
+        SaveMetadata {
+            location: location_name(save),
+            party: save
+                .party()
+                .species_and_levels()
+                .into_iter()
+                .map(|(species, level)| PartyMember { species, level })
+                .collect(),
+            ..SaveMetadata::default()
+        }
+    }
+
+    pub fn load(save_path: &Path) -> io::Result<SaveMetadata> {
+        let text = fs::read_to_string(get_metadata_path(save_path))?;
+        toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// The metadata for the save at `save_path`, falling back to what can be
+    /// read from `save` if there is no usable sidecar.
+    pub fn load_or_from_save(save_path: &Path, save: &SaveState) -> SaveMetadata {
+        match SaveMetadata::load(save_path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    log::error!("Error reading metadata for {}: {}", save_path.display(), e);
+                }
+
+                SaveMetadata::from_save(save)
+            }
+        }
+    }
+
+    fn write(&self, save_path: &Path) -> io::Result<()> {
+        let text =
+            toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(get_metadata_path(save_path), text)
+    }
+}
+
+/// Update the metadata and thumbnail of the save at `save_path`, after `save`
+/// has been written to it. `new_save` is whether this was the first time, and
+/// `frame` is the Game Boy screen to use as the thumbnail.
+pub fn update_metadata(
+    save_path: &Path,
+    save: &SaveState,
+    new_save: bool,
+    frame: &[u8],
+) -> io::Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let previous = if new_save {
+        None
+    } else {
+        SaveMetadata::load(save_path).ok()
+    };
+
+    let metadata = SaveMetadata {
+        created: previous.as_ref().map_or(now, |previous| previous.created),
+        last_played: now,
+        starter: if new_save {
+            Some(settings().starter)
+        } else {
+            previous.and_then(|previous| previous.starter)
+        },
+        ..SaveMetadata::from_save(save)
+    };
+
+    metadata.write(save_path)?;
+    screenshots::save_png(
+        &get_thumbnail_path(save_path),
+        frame,
+        GB_SCREEN_W,
+        GB_SCREEN_H,
+    )
+}
+
+/// Update what the metadata of the save at `save_path` says about `save`, e.g.
+/// after it was edited outside the game, keeping the rest as it was.
+pub fn refresh_metadata(save_path: &Path, save: &SaveState) -> io::Result<()> {
+    let previous = match SaveMetadata::load(save_path) {
+        Ok(previous) => previous,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    SaveMetadata {
+        created: previous.created,
+        last_played: previous.last_played,
+        starter: previous.starter,
+        ..SaveMetadata::from_save(save)
+    }
+    .write(save_path)
+}
+
+/// Indoor maps don't have a name of their own on the town map, so use the
+/// outdoor map the player came from.
+fn location_name(save: &SaveState) -> Option<String> {
+    let map = match save.current_map() {
+        map if map < map_constants::FIRST_INDOOR_MAP => map,
+        _ => save.last_map(),
+    };
+
+    map_constants::outdoor_map_name(map)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let metadata = SaveMetadata {
+            created: 1,
+            last_played: 2,
+            starter: Some(PokemonSpecies::MrMime),
+            location: Some("ROUTE 22".to_owned()),
+            party: vec![PartyMember {
+                species: PokemonSpecies::NidoranF,
+                level: 5,
+            }],
+        };
+
+        let text = toml::to_string(&metadata).unwrap();
+        assert_eq!(toml::from_str::<SaveMetadata>(&text).unwrap(), metadata);
+    }
+}
@@ -1,13 +1,16 @@
-use super::{DeterminantValues, PartyPokemon, PokeString, PokemonSpecies};
+use super::{
+    moves, DeterminantValues, MoveSlot, PartyPokemon, PokeString, PokemonSpecies, PokemonType,
+    StatusCondition,
+};
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct BoxedPokemon {
     pub species: PokemonSpecies,
     pub hp: u16,
     pub level: u8,
-    pub status: u8,
-    pub type1: u8,
-    pub type2: u8,
+    pub status: StatusCondition,
+    pub type1: PokemonType,
+    pub type2: PokemonType,
     pub catch_rate: u8,
     pub moves: [u8; 4],
     pub ot_id: u16,
@@ -23,6 +26,12 @@ pub struct BoxedPokemon {
     pub nickname: Option<PokeString>,
 }
 
+impl BoxedPokemon {
+    pub fn move_slots(&self) -> [Option<MoveSlot>; 4] {
+        moves::move_slots(self.moves, self.pp)
+    }
+}
+
 impl From<PartyPokemon> for BoxedPokemon {
     fn from(pokemon: PartyPokemon) -> BoxedPokemon {
         BoxedPokemon {
@@ -92,9 +101,9 @@ impl BoxView<'_> {
             species,
             hp: u16::from_be_bytes([self.data[offset + 1], self.data[offset + 2]]),
             level: self.data[offset + 3],
-            status: self.data[offset + 4],
-            type1: self.data[offset + 5],
-            type2: self.data[offset + 6],
+            status: StatusCondition::from_bits(self.data[offset + 4]),
+            type1: PokemonType::from_byte(self.data[offset + 5]),
+            type2: PokemonType::from_byte(self.data[offset + 6]),
             catch_rate: self.data[offset + 7],
             moves: [
                 self.data[offset + 8],
@@ -162,9 +171,9 @@ impl BoxViewMut<'_> {
         self.data[offset + 1] = (pokemon.hp >> 8) as u8;
         self.data[offset + 2] = (pokemon.hp & 0xff) as u8;
         self.data[offset + 3] = pokemon.level;
-        self.data[offset + 4] = pokemon.status;
-        self.data[offset + 5] = pokemon.type1;
-        self.data[offset + 6] = pokemon.type2;
+        self.data[offset + 4] = pokemon.status.bits();
+        self.data[offset + 5] = pokemon.type1.into_index();
+        self.data[offset + 6] = pokemon.type2.into_index();
         self.data[offset + 7] = pokemon.catch_rate;
         self.data[offset + 8] = pokemon.moves[0];
         self.data[offset + 9] = pokemon.moves[1];
@@ -273,3 +282,28 @@ impl<'a> Iterator for BoxIter<'a> {
         result
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unknown_type() {
+        let mut data = vec![0x50; 1122];
+        data[0] = 1;
+        data[1] = PokemonSpecies::Pikachu.into_index();
+        data[2] = 0xff;
+        data[22..55].fill(0);
+        data[22] = PokemonSpecies::Pikachu.into_index();
+        data[27] = 0x09;
+        data[28] = 0x09;
+
+        let pokemon = BoxView::new(&data).get(0).unwrap();
+        assert_eq!(pokemon.type1, PokemonType::Unknown(0x09));
+        assert_eq!(pokemon.type2, PokemonType::Unknown(0x09));
+
+        let before = data.clone();
+        BoxViewMut::new(&mut data).set(0, pokemon);
+        assert_eq!(data, before);
+    }
+}
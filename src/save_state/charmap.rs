@@ -0,0 +1,197 @@
+//! The characters of the English Gen 1 games, shared by [`PokeString`] and the
+//! text drawn from Rust.
+//!
+//! [`PokeString`]: super::PokeString
+
+/// Where a character is found in the UI atlases, as a tile position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glyph {
+    Font(usize, usize),
+    BoxBorder(usize, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharmapEntry {
+    pub byte: u8,
+    /// The text this byte stands for. Ligatures such as `'d` and words such as
+    /// `POKé` expand to more than one character.
+    pub text: &'static str,
+    /// The tile that draws this byte, or `None` if it is drawn as `text`, one
+    /// character at a time.
+    pub glyph: Option<Glyph>,
+}
+
+const fn entry(byte: u8, text: &'static str, glyph: Option<Glyph>) -> CharmapEntry {
+    CharmapEntry { byte, text, glyph }
+}
+
+/// Every printable character, sorted by byte. Control codes, and the unused
+/// hiragana and bold letters, are left out. The bold 𝐕 and 𝐒 are drawn with the
+/// regular letters.
+pub const CHARMAP: &[CharmapEntry] = &[
+    entry(0x4a, "𝔭𝔪", None),
+    entry(0x54, "POKé", None),
+    entry(0x56, "……", None),
+    entry(0x5b, "PC", None),
+    entry(0x5c, "TM", None),
+    entry(0x5d, "TRAINER", None),
+    entry(0x5e, "ROCKET", None),
+    entry(0x69, "𝐕", Some(Glyph::Font(5, 1))),
+    entry(0x6a, "𝐒", Some(Glyph::Font(2, 1))),
+    entry(0x6d, ":", Some(Glyph::Font(12, 1))),
+    entry(0x70, "‘", Some(Glyph::Font(6, 4))),
+    entry(0x71, "’", Some(Glyph::Font(0, 6))),
+    entry(0x72, "“", Some(Glyph::Font(7, 4))),
+    entry(0x73, "”", Some(Glyph::Font(8, 4))),
+    entry(0x74, "·", Some(Glyph::Font(9, 4))),
+    entry(0x75, "…", Some(Glyph::Font(10, 4))),
+    entry(0x79, "┌", Some(Glyph::BoxBorder(0, 0))),
+    entry(0x7a, "─", Some(Glyph::BoxBorder(1, 0))),
+    entry(0x7b, "┐", Some(Glyph::BoxBorder(2, 0))),
+    entry(0x7c, "│", Some(Glyph::BoxBorder(0, 1))),
+    entry(0x7d, "└", Some(Glyph::BoxBorder(0, 2))),
+    entry(0x7e, "┘", Some(Glyph::BoxBorder(2, 2))),
+    entry(0x7f, " ", Some(Glyph::Font(15, 3))),
+    entry(0x80, "A", Some(Glyph::Font(0, 0))),
+    entry(0x81, "B", Some(Glyph::Font(1, 0))),
+    entry(0x82, "C", Some(Glyph::Font(2, 0))),
+    entry(0x83, "D", Some(Glyph::Font(3, 0))),
+    entry(0x84, "E", Some(Glyph::Font(4, 0))),
+    entry(0x85, "F", Some(Glyph::Font(5, 0))),
+    entry(0x86, "G", Some(Glyph::Font(6, 0))),
+    entry(0x87, "H", Some(Glyph::Font(7, 0))),
+    entry(0x88, "I", Some(Glyph::Font(8, 0))),
+    entry(0x89, "J", Some(Glyph::Font(9, 0))),
+    entry(0x8a, "K", Some(Glyph::Font(10, 0))),
+    entry(0x8b, "L", Some(Glyph::Font(11, 0))),
+    entry(0x8c, "M", Some(Glyph::Font(12, 0))),
+    entry(0x8d, "N", Some(Glyph::Font(13, 0))),
+    entry(0x8e, "O", Some(Glyph::Font(14, 0))),
+    entry(0x8f, "P", Some(Glyph::Font(15, 0))),
+    entry(0x90, "Q", Some(Glyph::Font(0, 1))),
+    entry(0x91, "R", Some(Glyph::Font(1, 1))),
+    entry(0x92, "S", Some(Glyph::Font(2, 1))),
+    entry(0x93, "T", Some(Glyph::Font(3, 1))),
+    entry(0x94, "U", Some(Glyph::Font(4, 1))),
+    entry(0x95, "V", Some(Glyph::Font(5, 1))),
+    entry(0x96, "W", Some(Glyph::Font(6, 1))),
+    entry(0x97, "X", Some(Glyph::Font(7, 1))),
+    entry(0x98, "Y", Some(Glyph::Font(8, 1))),
+    entry(0x99, "Z", Some(Glyph::Font(9, 1))),
+    entry(0x9a, "(", Some(Glyph::Font(10, 1))),
+    entry(0x9b, ")", Some(Glyph::Font(11, 1))),
+    entry(0x9c, ":", Some(Glyph::Font(12, 1))),
+    entry(0x9d, ";", Some(Glyph::Font(13, 1))),
+    entry(0x9e, "[", Some(Glyph::Font(14, 1))),
+    entry(0x9f, "]", Some(Glyph::Font(15, 1))),
+    entry(0xa0, "a", Some(Glyph::Font(0, 2))),
+    entry(0xa1, "b", Some(Glyph::Font(1, 2))),
+    entry(0xa2, "c", Some(Glyph::Font(2, 2))),
+    entry(0xa3, "d", Some(Glyph::Font(3, 2))),
+    entry(0xa4, "e", Some(Glyph::Font(4, 2))),
+    entry(0xa5, "f", Some(Glyph::Font(5, 2))),
+    entry(0xa6, "g", Some(Glyph::Font(6, 2))),
+    entry(0xa7, "h", Some(Glyph::Font(7, 2))),
+    entry(0xa8, "i", Some(Glyph::Font(8, 2))),
+    entry(0xa9, "j", Some(Glyph::Font(9, 2))),
+    entry(0xaa, "k", Some(Glyph::Font(10, 2))),
+    entry(0xab, "l", Some(Glyph::Font(11, 2))),
+    entry(0xac, "m", Some(Glyph::Font(12, 2))),
+    entry(0xad, "n", Some(Glyph::Font(13, 2))),
+    entry(0xae, "o", Some(Glyph::Font(14, 2))),
+    entry(0xaf, "p", Some(Glyph::Font(15, 2))),
+    entry(0xb0, "q", Some(Glyph::Font(0, 3))),
+    entry(0xb1, "r", Some(Glyph::Font(1, 3))),
+    entry(0xb2, "s", Some(Glyph::Font(2, 3))),
+    entry(0xb3, "t", Some(Glyph::Font(3, 3))),
+    entry(0xb4, "u", Some(Glyph::Font(4, 3))),
+    entry(0xb5, "v", Some(Glyph::Font(5, 3))),
+    entry(0xb6, "w", Some(Glyph::Font(6, 3))),
+    entry(0xb7, "x", Some(Glyph::Font(7, 3))),
+    entry(0xb8, "y", Some(Glyph::Font(8, 3))),
+    entry(0xb9, "z", Some(Glyph::Font(9, 3))),
+    entry(0xba, "é", Some(Glyph::Font(10, 3))),
+    entry(0xbb, "'d", Some(Glyph::Font(0, 5))),
+    entry(0xbc, "'l", Some(Glyph::Font(1, 5))),
+    entry(0xbd, "'s", Some(Glyph::Font(4, 5))),
+    entry(0xbe, "'t", Some(Glyph::Font(5, 5))),
+    entry(0xbf, "'v", Some(Glyph::Font(6, 5))),
+    entry(0xe0, "'", Some(Glyph::Font(0, 6))),
+    entry(0xe1, "𝔭", Some(Glyph::Font(1, 6))),
+    entry(0xe2, "𝔪", Some(Glyph::Font(2, 6))),
+    entry(0xe3, "-", Some(Glyph::Font(3, 6))),
+    entry(0xe4, "'r", Some(Glyph::Font(3, 5))),
+    entry(0xe5, "'m", Some(Glyph::Font(2, 5))),
+    entry(0xe6, "?", Some(Glyph::Font(6, 6))),
+    entry(0xe7, "!", Some(Glyph::Font(7, 6))),
+    entry(0xe8, ".", Some(Glyph::Font(8, 6))),
+    entry(0xec, "▷", Some(Glyph::Font(12, 6))),
+    entry(0xed, "▶", Some(Glyph::Font(13, 6))),
+    entry(0xee, "▼", Some(Glyph::Font(14, 6))),
+    entry(0xef, "♂", Some(Glyph::Font(15, 6))),
+    entry(0xf0, "¥", Some(Glyph::Font(0, 7))),
+    entry(0xf1, "×", Some(Glyph::Font(1, 7))),
+    entry(0xf2, ".", Some(Glyph::Font(2, 7))),
+    entry(0xf3, "/", Some(Glyph::Font(3, 7))),
+    entry(0xf4, ",", Some(Glyph::Font(4, 7))),
+    entry(0xf5, "♀", Some(Glyph::Font(5, 7))),
+    entry(0xf6, "0", Some(Glyph::Font(6, 7))),
+    entry(0xf7, "1", Some(Glyph::Font(7, 7))),
+    entry(0xf8, "2", Some(Glyph::Font(8, 7))),
+    entry(0xf9, "3", Some(Glyph::Font(9, 7))),
+    entry(0xfa, "4", Some(Glyph::Font(10, 7))),
+    entry(0xfb, "5", Some(Glyph::Font(11, 7))),
+    entry(0xfc, "6", Some(Glyph::Font(12, 7))),
+    entry(0xfd, "7", Some(Glyph::Font(13, 7))),
+    entry(0xfe, "8", Some(Glyph::Font(14, 7))),
+    entry(0xff, "9", Some(Glyph::Font(15, 7))),
+];
+
+/// Drawn in place of characters that aren't in the font.
+pub const REPLACEMENT_GLYPH: Glyph = Glyph::Font(11, 4);
+
+/// Written in place of bytes that aren't printable.
+pub const REPLACEMENT_CHAR: char = '�';
+
+pub fn lookup_byte(byte: u8) -> Option<&'static CharmapEntry> {
+    CHARMAP
+        .binary_search_by_key(&byte, |entry| entry.byte)
+        .ok()
+        .map(|idx| &CHARMAP[idx])
+}
+
+/// Bytes that stand for the same text as another byte, but that the naming
+/// screen never writes: the small colon `<COLON>`, and the decimal point
+/// `<DOT>`.
+const DECODE_ONLY: &[u8] = &[0x6d, 0xf2];
+
+/// The entry that stands for exactly `chr`, preferring the byte the naming
+/// screen writes when more than one does.
+pub fn lookup_char(chr: char) -> Option<&'static CharmapEntry> {
+    let mut buf = [0; 4];
+    let text = chr.encode_utf8(&mut buf);
+
+    CHARMAP
+        .iter()
+        .find(|entry| entry.text == text && !DECODE_ONLY.contains(&entry.byte))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_charmap_is_sorted() {
+        assert!(CHARMAP.windows(2).all(|w| w[0].byte < w[1].byte));
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup_byte(0x54).unwrap().text, "POKé");
+        assert_eq!(lookup_byte(0xbb).unwrap().text, "'d");
+        assert_eq!(lookup_char('A').unwrap().byte, 0x80);
+        assert_eq!(lookup_char('.').unwrap().byte, 0xe8);
+        assert_eq!(lookup_char(':').unwrap().byte, 0x9c);
+        assert!(lookup_byte(0x00).is_none());
+    }
+}
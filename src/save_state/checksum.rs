@@ -0,0 +1,146 @@
+use super::SaveState;
+
+/// `sPlayerName`, the start of the data covered by the main checksum.
+pub(super) const MAIN_DATA_START: usize = 0x2598;
+/// `sMainDataCheckSum`, right after the data it covers.
+const MAIN_DATA_CHECKSUM: usize = 0x3523;
+
+/// `wCurrentBoxNum` in the saved main data. Bit 7 is set once the player has
+/// changed boxes, which is when the game first fills in the box banks.
+pub(super) const CURRENT_BOX_NUM: usize = 0x284c;
+pub(super) const BIT_HAS_CHANGED_BOXES: u8 = 7;
+
+/// Start of SRAM banks 2 and 3, which hold six boxes each.
+const BOX_BANKS: [usize; 2] = [0x4000, 0x6000];
+const BOXES_PER_BANK: usize = 6;
+const BOX_SIZE: usize = 0x462;
+/// `sBank2AllBoxesChecksum`, relative to the bank. It is followed by one
+/// checksum per box.
+const ALL_BOXES_CHECKSUM: usize = BOXES_PER_BANK * BOX_SIZE;
+
+/// A part of SRAM that the game protects with a checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumRegion {
+    /// Player name, main data, sprite data, party and current box.
+    MainData,
+    /// All six boxes in SRAM bank 2 or 3.
+    BoxBank(usize),
+    /// A single box, numbered from 0.
+    Box(usize),
+}
+
+impl ChecksumRegion {
+    /// The bytes covered by the checksum, and where the checksum is stored.
+    fn range(self) -> (std::ops::Range<usize>, usize) {
+        match self {
+            ChecksumRegion::MainData => (MAIN_DATA_START..MAIN_DATA_CHECKSUM, MAIN_DATA_CHECKSUM),
+            ChecksumRegion::BoxBank(bank) => {
+                let start = BOX_BANKS[bank];
+                (
+                    start..start + ALL_BOXES_CHECKSUM,
+                    start + ALL_BOXES_CHECKSUM,
+                )
+            }
+            ChecksumRegion::Box(idx) => {
+                let bank = BOX_BANKS[idx / BOXES_PER_BANK];
+                let start = bank + (idx % BOXES_PER_BANK) * BOX_SIZE;
+                let checksum = bank + ALL_BOXES_CHECKSUM + 1 + idx % BOXES_PER_BANK;
+                (start..start + BOX_SIZE, checksum)
+            }
+        }
+    }
+}
+
+/// The checksum the game uses: the complement of the sum of all bytes.
+fn checksum(bytes: &[u8]) -> u8 {
+    !bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+impl SaveState {
+    /// The regions with a checksum, leaving out the box banks until the game
+    /// has initialized them.
+    fn checksum_regions(&self) -> Vec<ChecksumRegion> {
+        let mut regions = vec![ChecksumRegion::MainData];
+
+        if self.data[CURRENT_BOX_NUM] & (1 << BIT_HAS_CHANGED_BOXES) != 0 {
+            regions.extend((0..BOX_BANKS.len()).map(ChecksumRegion::BoxBank));
+            regions.extend((0..BOX_BANKS.len() * BOXES_PER_BANK).map(ChecksumRegion::Box));
+        }
+
+        regions
+    }
+
+    /// The regions whose stored checksum doesn't match their data.
+    pub fn invalid_checksums(&self) -> Vec<ChecksumRegion> {
+        self.checksum_regions()
+            .into_iter()
+            .filter(|region| {
+                let (range, stored) = region.range();
+                checksum(&self.data[range]) != self.data[stored]
+            })
+            .collect()
+    }
+
+    pub fn checksums_valid(&self) -> bool {
+        self.invalid_checksums().is_empty()
+    }
+
+    /// Recompute all checksums, e.g. after changing SRAM from Rust.
+    pub fn update_checksums(&mut self) {
+        for region in self.checksum_regions() {
+            let (range, stored) = region.range();
+            self.data[stored] = checksum(&self.data[range]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_main_data_checksum() {
+        let mut save = SaveState::new();
+        assert_eq!(save.invalid_checksums(), vec![ChecksumRegion::MainData]);
+
+        save.update_checksums();
+        assert_eq!(save.byte(MAIN_DATA_CHECKSUM), 0xff);
+        assert!(save.checksums_valid());
+
+        save.set_byte(MAIN_DATA_START, 0x80);
+        assert!(!save.checksums_valid());
+
+        save.update_checksums();
+        assert_eq!(save.byte(MAIN_DATA_CHECKSUM), 0x7f);
+        assert!(save.checksums_valid());
+    }
+
+    #[test]
+    fn test_box_checksums() {
+        let mut save = SaveState::new();
+        save.set_byte(CURRENT_BOX_NUM, 1 << BIT_HAS_CHANGED_BOXES);
+        save.update_checksums();
+        assert!(save.checksums_valid());
+
+        // Number of mons in box 8, the second box in bank 3
+        save.set_byte(0x6462, 1);
+
+        assert_eq!(
+            save.invalid_checksums(),
+            vec![ChecksumRegion::BoxBank(1), ChecksumRegion::Box(7)]
+        );
+
+        save.update_checksums();
+        assert!(save.checksums_valid());
+    }
+
+    #[test]
+    fn test_box_banks_ignored_until_initialized() {
+        let mut save = SaveState::new();
+        save.set_byte(0x4000, 1);
+        save.update_checksums();
+
+        assert!(save.checksums_valid());
+        assert_eq!(save.byte(0x4000 + ALL_BOXES_CHECKSUM), 0);
+    }
+}
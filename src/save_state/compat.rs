@@ -0,0 +1,133 @@
+use std::io;
+
+use super::{
+    checksum::{BIT_HAS_CHANGED_BOXES, CURRENT_BOX_NUM, MAIN_DATA_START},
+    BoxId, SaveState, SAVE_SIZE,
+};
+
+/// The boxes in SRAM banks 2 and 3, in the order of `wCurrentBoxNum`.
+const SRAM_BOXES: [BoxId; 12] = [
+    BoxId::Box1,
+    BoxId::Box2,
+    BoxId::Box3,
+    BoxId::Box4,
+    BoxId::Box5,
+    BoxId::Box6,
+    BoxId::Box7,
+    BoxId::Box8,
+    BoxId::Box9,
+    BoxId::Box10,
+    BoxId::Box11,
+    BoxId::Box12,
+];
+
+const NAME_LENGTH: usize = 11;
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl SaveState {
+    /// Read a save written by the original game, e.g. from another emulator
+    /// or a cartridge dump, and bring its boxes into the state that Bill's PC
+    /// expects.
+    pub fn import(bytes: &[u8]) -> io::Result<SaveState> {
+        if bytes.len() < SAVE_SIZE {
+            return Err(invalid_data("File is too small to be a save"));
+        }
+
+        if bytes.len() > SAVE_SIZE {
+            log::warn!(
+                "Ignoring {} bytes after the end of SRAM",
+                bytes.len() - SAVE_SIZE
+            );
+        }
+
+        let mut result = SaveState::new();
+        result.data.copy_from_slice(&bytes[..SAVE_SIZE]);
+
+        let name = &result.data[MAIN_DATA_START..MAIN_DATA_START + NAME_LENGTH];
+        if name[0] == 0x50 || !name.contains(&0x50) {
+            return Err(invalid_data("File doesn't contain a player name"));
+        }
+
+        if !result.checksums_valid() {
+            return Err(invalid_data("File has invalid checksums"));
+        }
+
+        result.normalize_boxes();
+
+        Ok(result)
+    }
+
+    /// The original game keeps a stale copy of the current box in its slot in
+    /// SRAM, and leaves the box banks uninitialized until the player first
+    /// changes boxes, while Bill's PC shows every slot. Clear the slots that
+    /// Bill's PC shouldn't show, and update the checksums.
+    fn normalize_boxes(&mut self) {
+        let current_box = self.data[CURRENT_BOX_NUM];
+
+        if current_box & (1 << BIT_HAS_CHANGED_BOXES) == 0 {
+            for id in SRAM_BOXES {
+                self.box_mut(id).clear();
+            }
+
+            self.data[CURRENT_BOX_NUM] = current_box | (1 << BIT_HAS_CHANGED_BOXES);
+        } else {
+            let current_box_number = (current_box & 0x7f) as usize;
+
+            if let Some(&id) = SRAM_BOXES.get(current_box_number) {
+                self.box_mut(id).clear();
+            }
+        }
+
+        self.update_checksums();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn original_save(current_box: u8) -> SaveState {
+        let mut save = SaveState::new();
+        save.data[MAIN_DATA_START] = 0x80;
+        save.data[MAIN_DATA_START + 1] = 0x50;
+        save.data[CURRENT_BOX_NUM] = current_box;
+
+        for id in SRAM_BOXES {
+            save.data[id.sram_offset()] = 3;
+        }
+
+        save.update_checksums();
+        save
+    }
+
+    #[test]
+    fn test_import_initializes_boxes() {
+        let save = SaveState::import(original_save(0).as_bytes()).unwrap();
+
+        assert!(save.checksums_valid());
+        assert_eq!(save.byte(CURRENT_BOX_NUM), 0x80);
+        assert!(SRAM_BOXES.iter().all(|&id| save.r#box(id).len() == 0));
+    }
+
+    #[test]
+    fn test_import_clears_current_box_slot() {
+        let save = SaveState::import(original_save(0x82).as_bytes()).unwrap();
+
+        assert!(save.checksums_valid());
+        assert_eq!(save.r#box(BoxId::Box3).len(), 0);
+        assert_eq!(save.r#box(BoxId::Box4).len(), 3);
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_files() {
+        assert!(SaveState::import(&[0; 0x2000]).is_err());
+        assert!(SaveState::import(SaveState::new().as_bytes()).is_err());
+
+        let mut save = original_save(0);
+        save.data[MAIN_DATA_START + 2] = 1;
+        assert!(SaveState::import(save.as_bytes()).is_err());
+    }
+}
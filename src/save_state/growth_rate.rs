@@ -0,0 +1,178 @@
+use crate::rom::ROM;
+
+use super::{
+    party::{BASE_DATA_SIZE, BASE_STATS},
+    PokemonSpecies,
+};
+
+pub const MAX_LEVEL: u8 = 100;
+
+/// How much experience a species needs for each level, from `GrowthRateTable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum GrowthRate {
+    MediumFast = 0,
+    SlightlyFast = 1,
+    SlightlySlow = 2,
+    MediumSlow = 3,
+    Fast = 4,
+    Slow = 5,
+}
+
+impl GrowthRate {
+    pub fn from_index(index: u8) -> Option<GrowthRate> {
+        match index {
+            0 => Some(GrowthRate::MediumFast),
+            1 => Some(GrowthRate::SlightlyFast),
+            2 => Some(GrowthRate::SlightlySlow),
+            3 => Some(GrowthRate::MediumSlow),
+            4 => Some(GrowthRate::Fast),
+            5 => Some(GrowthRate::Slow),
+            _ => None,
+        }
+    }
+
+    /// `a/b n³ + c n² + d n - e`, as `(a, b, c, d, e)`.
+    fn coefficients(self) -> (i32, i32, i32, i32, i32) {
+        match self {
+            GrowthRate::MediumFast => (1, 1, 0, 0, 0),
+            GrowthRate::SlightlyFast => (3, 4, 10, 0, 30),
+            GrowthRate::SlightlySlow => (3, 4, 20, 0, 70),
+            GrowthRate::MediumSlow => (6, 5, -15, 100, 140),
+            GrowthRate::Fast => (4, 5, 0, 0, 0),
+            GrowthRate::Slow => (5, 4, 0, 0, 0),
+        }
+    }
+
+    /// The experience needed to reach `level`, as in `CalcExperience`. The
+    /// game works in 3 bytes, so this wraps around the same way, e.g. Medium
+    /// Slow at level 1 needs 16777162.
+    pub fn exp_for_level(self, level: u8) -> u32 {
+        let (a, b, c, d, e) = self.coefficients();
+        let n = level as i32;
+        let exp = a * n * n * n / b + c * n * n + d * n - e;
+
+        exp as u32 & 0xff_ffff
+    }
+
+    /// The level a Pokémon with `exp` experience is at, as in
+    /// `CalcLevelFromExperience`, but never above [`MAX_LEVEL`].
+    pub fn level_for_exp(self, exp: u32) -> u8 {
+        (2..=MAX_LEVEL)
+            .find(|&level| self.exp_for_level(level) > exp)
+            .map_or(MAX_LEVEL, |level| level - 1)
+    }
+}
+
+impl PokemonSpecies {
+    pub fn growth_rate(self) -> GrowthRate {
+        let index = ROM[BASE_STATS + (BASE_DATA_SIZE * (self as usize - 1)) + 19];
+        GrowthRate::from_index(index).expect("Invalid growth rate in ROM")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALL: [GrowthRate; 6] = [
+        GrowthRate::MediumFast,
+        GrowthRate::SlightlyFast,
+        GrowthRate::SlightlySlow,
+        GrowthRate::MediumSlow,
+        GrowthRate::Fast,
+        GrowthRate::Slow,
+    ];
+
+    #[test]
+    fn test_exp_for_level() {
+        // What `CalcExperience` gives in the original
+        let expected = [
+            (GrowthRate::MediumFast, [1, 8, 125, 1_000_000]),
+            (GrowthRate::SlightlyFast, [0xff_ffec, 16, 313, 849_970]),
+            (GrowthRate::SlightlySlow, [0xff_ffce, 16, 523, 949_930]),
+            (GrowthRate::MediumSlow, [0xff_ffca, 9, 135, 1_059_860]),
+            (GrowthRate::Fast, [0, 6, 100, 800_000]),
+            (GrowthRate::Slow, [1, 10, 156, 1_250_000]),
+        ];
+
+        for (rate, exp) in expected {
+            let actual = [1, 2, 5, 100].map(|level| rate.exp_for_level(level));
+            assert_eq!(actual, exp, "{:?}", rate);
+        }
+    }
+
+    #[test]
+    fn test_level_for_exp() {
+        for rate in ALL {
+            for level in 2..=MAX_LEVEL {
+                let exp = rate.exp_for_level(level);
+                assert_eq!(rate.level_for_exp(exp), level, "{:?}", rate);
+                assert_eq!(rate.level_for_exp(exp - 1), level - 1, "{:?}", rate);
+            }
+
+            assert_eq!(rate.level_for_exp(0), 1);
+            assert_eq!(rate.level_for_exp(0xff_ffff), MAX_LEVEL);
+        }
+    }
+
+    /// `GrowthRateTable` in the ROM, found by what it should contain: `a` and
+    /// `b` in one byte, `c` as sign and magnitude, then `d` and `e`.
+    fn rom_growth_rate_table() -> &'static [u8] {
+        let expected = ALL
+            .iter()
+            .flat_map(|rate| {
+                let (a, b, c, d, e) = rate.coefficients();
+                let c = if c < 0 { 0x80 | -c } else { c };
+                [(a << 4 | b) as u8, c as u8, d as u8, e as u8]
+            })
+            .collect::<Vec<_>>();
+
+        let offset = ROM
+            .windows(expected.len())
+            .position(|window| window == expected)
+            .expect("GrowthRateTable in the ROM doesn't match the coefficients");
+
+        &ROM[offset..offset + expected.len()]
+    }
+
+    /// What `CalcExperience` does with an entry of `GrowthRateTable`.
+    fn calc_experience(entry: &[u8], level: u8) -> u32 {
+        let n = level as u32;
+        let (a, b) = ((entry[0] >> 4) as u32, (entry[0] & 0xf) as u32);
+        let c = (entry[1] & 0x7f) as u32 * n * n;
+
+        let mut exp = a * n * n * n / b;
+        exp = match entry[1] & 0x80 {
+            0 => exp.wrapping_add(c),
+            _ => exp.wrapping_sub(c),
+        };
+        exp = exp.wrapping_add(entry[2] as u32 * n);
+        exp = exp.wrapping_sub(entry[3] as u32);
+
+        exp & 0xff_ffff
+    }
+
+    #[test]
+    fn test_matches_rom() {
+        let table = rom_growth_rate_table();
+
+        for species in (0..=u8::MAX).filter_map(PokemonSpecies::from_index) {
+            let index = ROM[BASE_STATS + (BASE_DATA_SIZE * (species as usize - 1)) + 19];
+            let entry = &table[index as usize * 4..][..4];
+            let rate = species.growth_rate();
+
+            assert_eq!(rate as u8, index, "{:?}", species);
+
+            for level in 1..=MAX_LEVEL {
+                assert_eq!(
+                    rate.exp_for_level(level),
+                    calc_experience(entry, level),
+                    "{:?} at level {}",
+                    species,
+                    level
+                );
+            }
+        }
+    }
+}
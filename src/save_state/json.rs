@@ -0,0 +1,1005 @@
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    checksum::{BIT_HAS_CHANGED_BOXES, CURRENT_BOX_NUM},
+    BoxId, BoxedPokemon, DeterminantValues, MoveSlot, NameField, PartyPokemon, PokeString,
+    PokemonSpecies, PokemonType, SaveState, StatusCondition, MAX_LEVEL,
+};
+use crate::settings::species_name;
+
+// Offsets into SRAM of the main data, named after the WRAM labels they are
+// copied from.
+const PLAYER_NAME: usize = 0x2598;
+/// `wPokedexOwned`, followed by `wPokedexSeen`.
+const POKEDEX_OWNED: usize = 0x25a3;
+const POKEDEX_SEEN: usize = 0x25b6;
+const POKEDEX_BYTES: usize = 19;
+/// `wNumBagItems`, followed by the items and a terminator.
+const BAG_ITEMS: usize = 0x25c9;
+const BAG_ITEM_CAPACITY: usize = 20;
+/// `wPlayerMoney`, 3 bytes of BCD.
+const PLAYER_MONEY: usize = 0x25f3;
+const RIVAL_NAME: usize = 0x25f6;
+const OBTAINED_BADGES: usize = 0x2602;
+const PLAYER_ID: usize = 0x2605;
+/// `wNumBoxItems`, followed by the items and a terminator.
+const PC_ITEMS: usize = 0x27e6;
+const PC_ITEM_CAPACITY: usize = 50;
+/// `wPlayerCoins`, 2 bytes of BCD.
+const PLAYER_COINS: usize = 0x2850;
+const EVENT_FLAGS: usize = 0x29f3;
+const NUM_EVENTS: usize = 0xa00;
+/// `wPlayTimeHours`, followed by `wPlayTimeMaxed`, minutes, seconds and
+/// frames.
+const PLAY_TIME: usize = 0x2ced;
+const PARTY_DATA: usize = 0x2f2c;
+
+const NAME_LENGTH: usize = 11;
+const PARTY_LENGTH: usize = 6;
+const BOX_CAPACITY: usize = 20;
+
+/// Valid item ids are the regular items, then the HMs and TMs.
+const NUM_ITEMS: u8 = 0x53;
+const HM01: u8 = 0xc4;
+const TM50: u8 = 0xfa;
+const MAX_ITEM_QUANTITY: u8 = 99;
+
+const MAX_MONEY: u32 = 999_999;
+const MAX_COINS: u16 = 9999;
+const MAX_DV: u8 = 15;
+
+const BADGES: [&str; 8] = [
+    "boulder", "cascade", "thunder", "rainbow", "soul", "marsh", "volcano", "earth",
+];
+
+const BOXES: [BoxId; 12] = [
+    BoxId::Box1,
+    BoxId::Box2,
+    BoxId::Box3,
+    BoxId::Box4,
+    BoxId::Box5,
+    BoxId::Box6,
+    BoxId::Box7,
+    BoxId::Box8,
+    BoxId::Box9,
+    BoxId::Box10,
+    BoxId::Box11,
+    BoxId::Box12,
+];
+
+/// Everything in a save that matters for the player's progress, in a form that
+/// is easy to read from other tools.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveDocument {
+    pub player: PlayerDocument,
+    pub party: Vec<PokemonDocument>,
+    /// The box that is selected in the PC, numbered from 1.
+    pub current_box: u8,
+    /// All 12 boxes, including the current one.
+    pub boxes: Vec<Vec<PokemonDocument>>,
+    pub bag: Vec<ItemStack>,
+    pub pc_items: Vec<ItemStack>,
+    pub pokedex: PokedexDocument,
+    /// The indices of the event flags that are set.
+    pub event_flags: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerDocument {
+    pub name: String,
+    pub id: u16,
+    pub rival_name: String,
+    pub money: u32,
+    pub coins: u16,
+    /// Names of the obtained badges, e.g. `boulder`.
+    pub badges: Vec<String>,
+    pub play_time: PlayTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayTime {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    /// Item id, as in the game's item constants.
+    pub item: u8,
+    pub quantity: u8,
+}
+
+/// Pokédex numbers of the species that have been owned and seen.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PokedexDocument {
+    pub owned: Vec<u8>,
+    pub seen: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PokemonDocument {
+    #[serde(with = "species_name")]
+    pub species: PokemonSpecies,
+    /// Only set if it differs from the species name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+    pub level: u8,
+    pub exp: u32,
+    pub hp: u16,
+    /// As stored in the save, see [`StatusCondition`].
+    pub status: StatusCondition,
+    pub types: [PokemonType; 2],
+    pub catch_rate: u8,
+    /// Move ids, with 0 for empty slots.
+    pub moves: [u8; 4],
+    /// Current PP, with the PP Ups used in the top two bits.
+    pub pp: [u8; 4],
+    pub ot_name: String,
+    pub ot_id: u16,
+    pub dvs: Dvs,
+    pub stat_exp: StatExp,
+    /// Only stored for Pokémon in the party, the game works them out again
+    /// when withdrawing from a box. Ignored by [`SaveState::apply_document`],
+    /// which works them out too.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<Stats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dvs {
+    pub attack: u8,
+    pub defense: u8,
+    pub speed: u8,
+    pub special: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatExp {
+    pub hp: u16,
+    pub attack: u16,
+    pub defense: u16,
+    pub speed: u16,
+    pub special: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    pub max_hp: u16,
+    pub attack: u16,
+    pub defense: u16,
+    pub speed: u16,
+    pub special: u16,
+}
+
+impl From<&BoxedPokemon> for PokemonDocument {
+    fn from(pokemon: &BoxedPokemon) -> PokemonDocument {
+        PokemonDocument {
+            species: pokemon.species,
+            nickname: pokemon.nickname.as_ref().map(|name| name.to_string()),
+            level: pokemon.level,
+            exp: pokemon.exp,
+            hp: pokemon.hp,
+            status: pokemon.status,
+            types: [pokemon.type1, pokemon.type2],
+            catch_rate: pokemon.catch_rate,
+            moves: pokemon.moves,
+            pp: pokemon.pp,
+            ot_name: pokemon.ot_name.to_string(),
+            ot_id: pokemon.ot_id,
+            dvs: Dvs::from(&pokemon.dvs),
+            stat_exp: StatExp {
+                hp: pokemon.hp_exp,
+                attack: pokemon.attack_exp,
+                defense: pokemon.defense_exp,
+                speed: pokemon.speed_exp,
+                special: pokemon.special_exp,
+            },
+            stats: None,
+        }
+    }
+}
+
+impl From<&PartyPokemon> for PokemonDocument {
+    fn from(pokemon: &PartyPokemon) -> PokemonDocument {
+        PokemonDocument {
+            stats: Some(Stats {
+                max_hp: pokemon.max_hp,
+                attack: pokemon.attack,
+                defense: pokemon.defense,
+                speed: pokemon.speed,
+                special: pokemon.special,
+            }),
+            ..PokemonDocument::from(&BoxedPokemon::from(pokemon.clone()))
+        }
+    }
+}
+
+impl From<&DeterminantValues> for Dvs {
+    fn from(dvs: &DeterminantValues) -> Dvs {
+        Dvs {
+            attack: dvs.attack(),
+            defense: dvs.defense(),
+            speed: dvs.speed(),
+            special: dvs.special(),
+        }
+    }
+}
+
+/// Decode big-endian binary-coded decimal, as used for money and coins.
+fn from_bcd(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |value, &byte| {
+        value * 100 + (byte >> 4) as u32 * 10 + (byte & 0x0f) as u32
+    })
+}
+
+/// Encode big-endian binary-coded decimal, the inverse of [`from_bcd`].
+fn to_bcd(mut value: u32, bytes: &mut [u8]) {
+    for byte in bytes.iter_mut().rev() {
+        *byte = (((value / 10 % 10) << 4) | (value % 10)) as u8;
+        value /= 100;
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl SaveState {
+    /// Decode the save into a [`SaveDocument`], failing if any of the Pokémon
+    /// lists are damaged.
+    pub fn to_document(&self) -> io::Result<SaveDocument> {
+        let current_box = self.data[CURRENT_BOX_NUM];
+        let boxes_initialized = current_box & (1 << BIT_HAS_CHANGED_BOXES) != 0;
+        let current_box = (current_box & 0x7f) as usize;
+
+        if current_box >= BOXES.len() {
+            return Err(invalid_data(format!("Invalid current box {}", current_box)));
+        }
+
+        self.check_mon_list("party", PARTY_DATA, PARTY_LENGTH, 8, 44)?;
+
+        let party = self.party().iter().map(|mon| (&mon).into()).collect();
+
+        let boxes = BOXES
+            .iter()
+            .enumerate()
+            .map(|(idx, &id)| {
+                // The current box is only written back to its bank when
+                // switching boxes, and the banks are garbage until then
+                let id = if idx == current_box {
+                    BoxId::Current
+                } else if boxes_initialized {
+                    id
+                } else {
+                    return Ok(Vec::new());
+                };
+
+                let name = format!("box {}", idx + 1);
+                self.check_mon_list(&name, id.sram_offset(), BOX_CAPACITY, 22, 33)?;
+
+                Ok(self.r#box(id).iter().map(|mon| (&mon).into()).collect())
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(SaveDocument {
+            player: PlayerDocument {
+                name: self.player_name().to_string(),
+                id: u16::from_be_bytes([self.data[PLAYER_ID], self.data[PLAYER_ID + 1]]),
+                rival_name: super::PokeString::from_bytes(&self.data[RIVAL_NAME..], NAME_LENGTH)
+                    .to_string(),
+                money: from_bcd(&self.data[PLAYER_MONEY..PLAYER_MONEY + 3]),
+                coins: from_bcd(&self.data[PLAYER_COINS..PLAYER_COINS + 2]) as u16,
+                badges: BADGES
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| self.data[OBTAINED_BADGES] & (1 << bit) != 0)
+                    .map(|(_, name)| name.to_string())
+                    .collect(),
+                play_time: PlayTime {
+                    hours: self.data[PLAY_TIME],
+                    minutes: self.data[PLAY_TIME + 2],
+                    seconds: self.data[PLAY_TIME + 3],
+                },
+            },
+            party,
+            current_box: current_box as u8 + 1,
+            boxes,
+            bag: self.item_list("bag", BAG_ITEMS, BAG_ITEM_CAPACITY)?,
+            pc_items: self.item_list("PC", PC_ITEMS, PC_ITEM_CAPACITY)?,
+            pokedex: PokedexDocument {
+                owned: self.pokedex_flags(POKEDEX_OWNED),
+                seen: self.pokedex_flags(POKEDEX_SEEN),
+            },
+            event_flags: (0..NUM_EVENTS)
+                .filter(|&flag| self.data[EVENT_FLAGS + flag / 8] & (1 << (flag % 8)) != 0)
+                .map(|flag| flag as u16)
+                .collect(),
+        })
+    }
+
+    /// Make sure that decoding a party or box at `start` won't panic, given
+    /// where the first Pokémon's data is and how long each one is.
+    fn check_mon_list(
+        &self,
+        name: &str,
+        start: usize,
+        capacity: usize,
+        first_mon: usize,
+        mon_size: usize,
+    ) -> io::Result<()> {
+        let count = self.data[start] as usize;
+
+        if count > capacity {
+            return Err(invalid_data(format!(
+                "Invalid {}: {} Pokémon, at most {} fit",
+                name, count, capacity
+            )));
+        }
+
+        for idx in 0..count {
+            let offset = start + first_mon + idx * mon_size;
+            let index = self.data[offset];
+
+            if self.data[start + 1 + idx] == 0xff || PokemonSpecies::from_index(index).is_none() {
+                return Err(invalid_data(format!(
+                    "Invalid {}: unknown species {:#04x} in slot {}",
+                    name,
+                    index,
+                    idx + 1
+                )));
+            }
+
+            for &type_index in &self.data[offset + 5..offset + 7] {
+                if PokemonType::from_index(type_index).is_none() {
+                    return Err(invalid_data(format!(
+                        "Invalid {}: unknown type {:#04x} in slot {}",
+                        name,
+                        type_index,
+                        idx + 1
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn item_list(&self, name: &str, start: usize, capacity: usize) -> io::Result<Vec<ItemStack>> {
+        let count = self.data[start] as usize;
+
+        if count > capacity {
+            return Err(invalid_data(format!(
+                "Invalid {} items: {} stacks, at most {} fit",
+                name, count, capacity
+            )));
+        }
+
+        Ok((0..count)
+            .map(|idx| ItemStack {
+                item: self.data[start + 1 + idx * 2],
+                quantity: self.data[start + 2 + idx * 2],
+            })
+            .collect())
+    }
+
+    fn pokedex_flags(&self, start: usize) -> Vec<u8> {
+        (0..POKEDEX_BYTES * 8)
+            .filter(|&bit| self.data[start + bit / 8] & (1 << (bit % 8)) != 0)
+            .map(|bit| bit as u8 + 1)
+            .filter(|&number| number <= 151)
+            .collect()
+    }
+}
+
+fn encode_name(what: &str, text: &str, field: NameField) -> io::Result<PokeString> {
+    PokeString::encode_name(text, field)
+        .map_err(|e| invalid_data(format!("Invalid {}: {:?} {}", what, text, e)))
+}
+
+fn check_item_list(name: &str, items: &[ItemStack], capacity: usize) -> io::Result<()> {
+    if items.len() > capacity {
+        return Err(invalid_data(format!(
+            "Invalid {} items: {} stacks, at most {} fit",
+            name,
+            items.len(),
+            capacity
+        )));
+    }
+
+    for stack in items {
+        if stack.item == 0 || (stack.item > NUM_ITEMS && stack.item < HM01) || stack.item > TM50 {
+            return Err(invalid_data(format!(
+                "Invalid {} items: unknown item {:#04x}",
+                name, stack.item
+            )));
+        }
+
+        if stack.quantity == 0 || stack.quantity > MAX_ITEM_QUANTITY {
+            return Err(invalid_data(format!(
+                "Invalid {} items: {} of item {:#04x}, must be 1 to {}",
+                name, stack.quantity, stack.item, MAX_ITEM_QUANTITY
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+impl PokemonDocument {
+    /// Check every field against what the game allows, and convert to the
+    /// form that is stored in a box.
+    fn to_boxed(&self, what: &str) -> io::Result<BoxedPokemon> {
+        let invalid = |message: String| invalid_data(format!("Invalid {}: {}", what, message));
+
+        if self.level == 0 || self.level > MAX_LEVEL {
+            return Err(invalid(format!(
+                "level {}, must be 1 to {}",
+                self.level, MAX_LEVEL
+            )));
+        }
+
+        let growth_rate = self.species.growth_rate();
+        let min_exp = match self.level {
+            1 => 0,
+            level => growth_rate.exp_for_level(level),
+        };
+        let max_exp = match self.level {
+            MAX_LEVEL => growth_rate.exp_for_level(MAX_LEVEL),
+            level => growth_rate.exp_for_level(level + 1) - 1,
+        };
+        if self.exp < min_exp || self.exp > max_exp {
+            return Err(invalid(format!(
+                "{} exp at level {}, must be {} to {}",
+                self.exp, self.level, min_exp, max_exp
+            )));
+        }
+
+        if self.types != self.species.types() {
+            return Err(invalid(format!(
+                "types {:?}, {} is {:?}",
+                self.types,
+                self.species.name(),
+                self.species.types()
+            )));
+        }
+
+        if !self.status.is_valid() {
+            return Err(invalid(format!(
+                "unknown status {:#04x}",
+                self.status.bits()
+            )));
+        }
+
+        let dvs = [
+            self.dvs.attack,
+            self.dvs.defense,
+            self.dvs.speed,
+            self.dvs.special,
+        ];
+        if dvs.iter().any(|&dv| dv > MAX_DV) {
+            return Err(invalid(format!("DVs must be 0 to {}", MAX_DV)));
+        }
+
+        for (slot, (&move_id, &pp)) in self.moves.iter().zip(&self.pp).enumerate() {
+            let slot = slot + 1;
+
+            if move_id == 0 {
+                if self.moves[slot..].iter().any(|&id| id != 0) {
+                    return Err(invalid(format!(
+                        "move slot {} is empty but not the last",
+                        slot
+                    )));
+                }
+
+                if pp != 0 {
+                    return Err(invalid(format!("{} PP for empty move slot {}", pp, slot)));
+                }
+
+                continue;
+            }
+
+            let move_slot = MoveSlot::from_bytes(move_id, pp)
+                .ok_or_else(|| invalid(format!("unknown move {} in slot {}", move_id, slot)))?;
+
+            if move_slot.pp > move_slot.max_pp() {
+                return Err(invalid(format!(
+                    "{} PP for {} in slot {}, at most {}",
+                    move_slot.pp,
+                    move_slot.r#move.name(),
+                    slot,
+                    move_slot.max_pp()
+                )));
+            }
+        }
+
+        let nickname = self
+            .nickname
+            .as_deref()
+            .map(|name| encode_name(&format!("{} nickname", what), name, NameField::Nickname))
+            .transpose()?;
+        let ot_name = encode_name(
+            &format!("{} OT name", what),
+            &self.ot_name,
+            NameField::Player,
+        )?;
+
+        let pokemon = BoxedPokemon {
+            species: self.species,
+            hp: self.hp,
+            level: self.level,
+            status: self.status,
+            type1: self.types[0],
+            type2: self.types[1],
+            catch_rate: self.catch_rate,
+            moves: self.moves,
+            ot_id: self.ot_id,
+            exp: self.exp,
+            hp_exp: self.stat_exp.hp,
+            attack_exp: self.stat_exp.attack,
+            defense_exp: self.stat_exp.defense,
+            speed_exp: self.stat_exp.speed,
+            special_exp: self.stat_exp.special,
+            dvs: DeterminantValues(
+                (self.dvs.attack << 4) | self.dvs.defense,
+                (self.dvs.speed << 4) | self.dvs.special,
+            ),
+            pp: self.pp,
+            ot_name,
+            nickname,
+        };
+
+        let max_hp = PartyPokemon::with_level(pokemon.clone(), self.level).max_hp;
+        if self.hp > max_hp {
+            return Err(invalid(format!("{} HP, at most {}", self.hp, max_hp)));
+        }
+
+        Ok(pokemon)
+    }
+}
+
+impl SaveState {
+    /// Write everything in `document` into the save, the inverse of
+    /// [`SaveState::to_document`]. The whole document is checked first, so
+    /// the save is left alone if anything is invalid. The stats of Pokémon in
+    /// the party are worked out again, and the checksums are updated.
+    pub fn apply_document(&mut self, document: &SaveDocument) -> io::Result<()> {
+        let player = &document.player;
+        let name = encode_name("player name", &player.name, NameField::Player)?;
+        let rival_name = encode_name("rival name", &player.rival_name, NameField::Player)?;
+
+        if player.money > MAX_MONEY {
+            return Err(invalid_data(format!(
+                "Invalid money: {}, at most {}",
+                player.money, MAX_MONEY
+            )));
+        }
+
+        if player.coins > MAX_COINS {
+            return Err(invalid_data(format!(
+                "Invalid coins: {}, at most {}",
+                player.coins, MAX_COINS
+            )));
+        }
+
+        let badges = player.badges.iter().try_fold(0u8, |badges, name| {
+            match BADGES.iter().position(|badge| badge == name) {
+                Some(bit) => Ok(badges | (1 << bit)),
+                None => Err(invalid_data(format!("Unknown badge {:?}", name))),
+            }
+        })?;
+
+        let play_time = player.play_time;
+        if play_time.minutes >= 60 || play_time.seconds >= 60 {
+            return Err(invalid_data(format!(
+                "Invalid play time {}:{}:{}",
+                play_time.hours, play_time.minutes, play_time.seconds
+            )));
+        }
+
+        if document.party.len() > PARTY_LENGTH {
+            return Err(invalid_data(format!(
+                "Invalid party: {} Pokémon, at most {} fit",
+                document.party.len(),
+                PARTY_LENGTH
+            )));
+        }
+
+        let party = document
+            .party
+            .iter()
+            .enumerate()
+            .map(|(idx, mon)| {
+                let pokemon = mon.to_boxed(&format!("party slot {}", idx + 1))?;
+                Ok(PartyPokemon::with_level(pokemon, mon.level))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if document.current_box == 0 || document.current_box as usize > BOXES.len() {
+            return Err(invalid_data(format!(
+                "Invalid current box {}, must be 1 to {}",
+                document.current_box,
+                BOXES.len()
+            )));
+        }
+
+        if document.boxes.len() != BOXES.len() {
+            return Err(invalid_data(format!(
+                "Invalid boxes: there must be {}, not {}",
+                BOXES.len(),
+                document.boxes.len()
+            )));
+        }
+
+        let boxes = document
+            .boxes
+            .iter()
+            .enumerate()
+            .map(|(idx, mons)| {
+                if mons.len() > BOX_CAPACITY {
+                    return Err(invalid_data(format!(
+                        "Invalid box {}: {} Pokémon, at most {} fit",
+                        idx + 1,
+                        mons.len(),
+                        BOX_CAPACITY
+                    )));
+                }
+
+                mons.iter()
+                    .enumerate()
+                    .map(|(slot, mon)| mon.to_boxed(&format!("box {} slot {}", idx + 1, slot + 1)))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        check_item_list("bag", &document.bag, BAG_ITEM_CAPACITY)?;
+        check_item_list("PC", &document.pc_items, PC_ITEM_CAPACITY)?;
+
+        let pokedex = document.pokedex.owned.iter().chain(&document.pokedex.seen);
+        if let Some(number) = pokedex.copied().find(|&number| number == 0 || number > 151) {
+            return Err(invalid_data(format!("Invalid Pokédex number {}", number)));
+        }
+
+        if let Some(&flag) = document
+            .event_flags
+            .iter()
+            .find(|&&flag| flag as usize >= NUM_EVENTS)
+        {
+            return Err(invalid_data(format!("Invalid event flag {}", flag)));
+        }
+
+        // Everything is valid, so start writing
+        self.write_name(PLAYER_NAME, &name);
+        self.write_name(RIVAL_NAME, &rival_name);
+        self.data[PLAYER_ID..PLAYER_ID + 2].copy_from_slice(&player.id.to_be_bytes());
+        to_bcd(player.money, &mut self.data[PLAYER_MONEY..PLAYER_MONEY + 3]);
+        to_bcd(
+            player.coins as u32,
+            &mut self.data[PLAYER_COINS..PLAYER_COINS + 2],
+        );
+        self.data[OBTAINED_BADGES] = badges;
+        self.data[PLAY_TIME] = play_time.hours;
+        self.data[PLAY_TIME + 2] = play_time.minutes;
+        self.data[PLAY_TIME + 3] = play_time.seconds;
+
+        let mut party_view = self.party_mut();
+        party_view.clear();
+        for pokemon in party {
+            party_view.push(pokemon);
+        }
+
+        // Only fill in the box banks if the game has, or if there is
+        // something to put in them
+        let current_box = document.current_box as usize - 1;
+        let boxes_initialized = self.data[CURRENT_BOX_NUM] & (1 << BIT_HAS_CHANGED_BOXES) != 0
+            || boxes
+                .iter()
+                .enumerate()
+                .any(|(idx, mons)| idx != current_box && !mons.is_empty());
+
+        // The current box lives in `Current`, and its bank is kept empty so
+        // that Bill's PC doesn't list its Pokémon twice
+        for (idx, mons) in boxes.iter().enumerate() {
+            let id = if idx == current_box {
+                if boxes_initialized {
+                    self.box_mut(BOXES[idx]).clear();
+                }
+                BoxId::Current
+            } else if boxes_initialized {
+                BOXES[idx]
+            } else {
+                continue;
+            };
+
+            let mut box_view = self.box_mut(id);
+            box_view.clear();
+            for pokemon in mons {
+                box_view.push(pokemon.clone());
+            }
+        }
+
+        self.data[CURRENT_BOX_NUM] = current_box as u8;
+        if boxes_initialized {
+            self.data[CURRENT_BOX_NUM] |= 1 << BIT_HAS_CHANGED_BOXES;
+        }
+
+        self.write_item_list(BAG_ITEMS, &document.bag);
+        self.write_item_list(PC_ITEMS, &document.pc_items);
+
+        self.write_pokedex_flags(POKEDEX_OWNED, &document.pokedex.owned);
+        self.write_pokedex_flags(POKEDEX_SEEN, &document.pokedex.seen);
+
+        self.data[EVENT_FLAGS..EVENT_FLAGS + NUM_EVENTS / 8].fill(0);
+        for &flag in &document.event_flags {
+            let flag = flag as usize;
+            self.data[EVENT_FLAGS + flag / 8] |= 1 << (flag % 8);
+        }
+
+        self.update_checksums();
+
+        Ok(())
+    }
+
+    /// Write `name` at `start`, padded with terminators.
+    fn write_name(&mut self, start: usize, name: &PokeString) {
+        let bytes = name.iter().chain(std::iter::repeat(0x50));
+        for (dest, byte) in self.data[start..start + NAME_LENGTH].iter_mut().zip(bytes) {
+            *dest = byte;
+        }
+    }
+
+    fn write_item_list(&mut self, start: usize, items: &[ItemStack]) {
+        self.data[start] = items.len() as u8;
+
+        for (idx, stack) in items.iter().enumerate() {
+            self.data[start + 1 + idx * 2] = stack.item;
+            self.data[start + 2 + idx * 2] = stack.quantity;
+        }
+
+        self.data[start + 1 + items.len() * 2] = 0xff;
+    }
+
+    fn write_pokedex_flags(&mut self, start: usize, numbers: &[u8]) {
+        self.data[start..start + POKEDEX_BYTES].fill(0);
+
+        for &number in numbers {
+            let bit = number as usize - 1;
+            self.data[start + bit / 8] |= 1 << (bit % 8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_bcd() {
+        assert_eq!(from_bcd(&[0x01, 0x23, 0x45]), 12345);
+        assert_eq!(from_bcd(&[0x99, 0x99]), 9999);
+    }
+
+    #[test]
+    fn test_empty_save_document() {
+        let mut save = SaveState::new();
+        save.set_byte(PLAYER_NAME, 0x50);
+        save.set_byte(RIVAL_NAME, 0x50);
+        save.set_byte(PLAYER_MONEY + 2, 0x42);
+        save.set_byte(OBTAINED_BADGES, 0b1000_0001);
+        save.set_byte(POKEDEX_OWNED, 0b0000_0001);
+        save.set_byte(EVENT_FLAGS + 1, 0b0000_0100);
+
+        let document = save.to_document().unwrap();
+
+        assert_eq!(document.player.money, 42);
+        assert_eq!(document.player.badges, vec!["boulder", "earth"]);
+        assert_eq!(document.pokedex.owned, vec![1]);
+        assert_eq!(document.event_flags, vec![10]);
+        assert_eq!(document.current_box, 1);
+        assert_eq!(document.boxes.len(), 12);
+        assert!(document.party.is_empty());
+    }
+
+    fn pokemon() -> PokemonDocument {
+        PokemonDocument {
+            species: PokemonSpecies::Pikachu,
+            nickname: Some("SPARKY".to_string()),
+            level: 5,
+            exp: 125,
+            hp: 10,
+            status: StatusCondition::NONE,
+            types: [PokemonType::Electric, PokemonType::Electric],
+            catch_rate: 190,
+            moves: [0; 4],
+            pp: [0; 4],
+            ot_name: "ASH".to_string(),
+            ot_id: 12345,
+            dvs: Dvs {
+                attack: 15,
+                defense: 1,
+                speed: 7,
+                special: 9,
+            },
+            stat_exp: StatExp {
+                hp: 0,
+                attack: 100,
+                defense: 0,
+                speed: 0,
+                special: 0,
+            },
+            stats: None,
+        }
+    }
+
+    fn document() -> SaveDocument {
+        let mut boxes = vec![Vec::new(); 12];
+        boxes[2].push(pokemon());
+
+        SaveDocument {
+            player: PlayerDocument {
+                name: "ASH".to_string(),
+                id: 12345,
+                rival_name: "GARY".to_string(),
+                money: 123456,
+                coins: 50,
+                badges: vec!["boulder".to_string(), "cascade".to_string()],
+                play_time: PlayTime {
+                    hours: 12,
+                    minutes: 34,
+                    seconds: 56,
+                },
+            },
+            party: vec![pokemon()],
+            current_box: 1,
+            boxes,
+            bag: vec![ItemStack {
+                item: 0x04,
+                quantity: 5,
+            }],
+            pc_items: vec![ItemStack {
+                item: 0x14,
+                quantity: 1,
+            }],
+            pokedex: PokedexDocument {
+                owned: vec![25],
+                seen: vec![16, 25],
+            },
+            event_flags: vec![0, 2559],
+        }
+    }
+
+    #[test]
+    fn test_to_bcd() {
+        let mut bytes = [0; 3];
+        to_bcd(12345, &mut bytes);
+        assert_eq!(bytes, [0x01, 0x23, 0x45]);
+    }
+
+    #[test]
+    fn test_apply_document_round_trip() {
+        let mut save = SaveState::new();
+        save.apply_document(&document()).unwrap();
+        assert!(save.checksums_valid());
+
+        let mut result = save.to_document().unwrap();
+        assert!(result.party[0].stats.is_some());
+        result.party[0].stats = None;
+
+        assert_eq!(result, document());
+    }
+
+    #[test]
+    fn test_apply_document_current_box() {
+        let mut boxes = vec![Vec::new(); 12];
+        boxes[0].push(pokemon());
+        boxes[2].push(pokemon());
+
+        let document = SaveDocument {
+            boxes,
+            current_box: 3,
+            ..document()
+        };
+
+        let mut save = SaveState::new();
+        save.apply_document(&document).unwrap();
+
+        assert_eq!(save.r#box(BoxId::Current).len(), 1);
+        assert_eq!(save.r#box(BOXES[2]).len(), 0);
+        assert_eq!(save.r#box(BOXES[0]).len(), 1);
+        assert_eq!(save.to_document().unwrap().boxes, document.boxes);
+    }
+
+    #[test]
+    fn test_apply_invalid_document() {
+        let invalid = [
+            SaveDocument {
+                party: vec![PokemonDocument {
+                    level: 101,
+                    ..pokemon()
+                }],
+                ..document()
+            },
+            SaveDocument {
+                party: vec![PokemonDocument {
+                    exp: 124,
+                    ..pokemon()
+                }],
+                ..document()
+            },
+            SaveDocument {
+                party: vec![PokemonDocument {
+                    exp: 216,
+                    ..pokemon()
+                }],
+                ..document()
+            },
+            SaveDocument {
+                party: vec![PokemonDocument {
+                    level: 100,
+                    exp: 1_000_001,
+                    ..pokemon()
+                }],
+                ..document()
+            },
+            SaveDocument {
+                party: vec![PokemonDocument {
+                    types: [PokemonType::Electric, PokemonType::Normal],
+                    ..pokemon()
+                }],
+                ..document()
+            },
+            SaveDocument {
+                party: vec![PokemonDocument {
+                    moves: [166, 0, 0, 0],
+                    ..pokemon()
+                }],
+                ..document()
+            },
+            SaveDocument {
+                party: vec![PokemonDocument {
+                    moves: [0, 1, 0, 0],
+                    ..pokemon()
+                }],
+                ..document()
+            },
+            SaveDocument {
+                party: vec![PokemonDocument {
+                    nickname: Some("ELEVENCHARS".to_string()),
+                    ..pokemon()
+                }],
+                ..document()
+            },
+            SaveDocument {
+                party: vec![pokemon(); 7],
+                ..document()
+            },
+            SaveDocument {
+                pc_items: vec![ItemStack {
+                    item: 0x60,
+                    quantity: 1,
+                }],
+                ..document()
+            },
+            SaveDocument {
+                event_flags: vec![2560],
+                ..document()
+            },
+        ];
+
+        for document in invalid {
+            let mut save = SaveState::new();
+            assert!(save.apply_document(&document).is_err());
+            assert!(save.as_bytes().iter().all(|&byte| byte == 0));
+        }
+    }
+
+    #[test]
+    fn test_damaged_party() {
+        let mut save = SaveState::new();
+        save.set_byte(PARTY_DATA, 7);
+
+        assert!(save.to_document().is_err());
+    }
+}
@@ -1,17 +1,31 @@
 use std::{
-    io::{self, Read},
+    fs,
+    io::{self, Read, Write},
     path::PathBuf,
 };
 
 mod r#box;
+pub mod charmap;
+mod checksum;
+mod compat;
+mod growth_rate;
+mod json;
+mod moves;
 mod party;
+mod pokemon_type;
 mod species;
+mod status;
 mod string;
 
+pub use growth_rate::{GrowthRate, MAX_LEVEL};
+pub use json::SaveDocument;
+pub use moves::{Move, MoveSlot};
 pub use party::{PartyPokemon, PartyView, PartyViewMut};
+pub use pokemon_type::{Effectiveness, PokemonType, TYPE_EFFECTS};
 pub use r#box::{BoxView, BoxViewMut, BoxedPokemon};
 pub use species::PokemonSpecies;
-pub use string::PokeString;
+pub use status::StatusCondition;
+pub use string::{EncodeError, NameField, PokeString};
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct DeterminantValues(u8, u8);
@@ -94,24 +108,45 @@ impl BoxId {
     }
 }
 
+/// Size of SRAM, and of a save file.
+const SAVE_SIZE: usize = 0x8000;
+
+#[derive(Clone)]
 pub struct SaveState {
-    data: [u8; 0x8000],
+    data: [u8; SAVE_SIZE],
 }
 
 impl SaveState {
     pub fn new() -> SaveState {
-        SaveState { data: [0; 0x8000] }
+        SaveState {
+            data: [0; SAVE_SIZE],
+        }
     }
 
     pub fn from_file(path: &PathBuf) -> io::Result<SaveState> {
         let mut file = std::fs::File::open(path)?;
-        let mut data = [0; 0x8000];
+        let mut data = [0; SAVE_SIZE];
         file.read_exact(&mut data)?;
         Ok(SaveState { data })
     }
 
+    /// Write to a temporary file next to `path` and rename it into place, so
+    /// that `path` holds either the old or the new save even if writing is
+    /// interrupted.
     pub fn write_to_file(&self, path: &PathBuf) -> io::Result<()> {
-        std::fs::write(path, &self.data)
+        let mut tmp_path = path.clone().into_os_string();
+        tmp_path.push(".tmp");
+
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&self.data)?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&tmp_path, path)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
     }
 
     pub fn byte(&self, addr: usize) -> u8 {
@@ -140,6 +175,24 @@ impl SaveState {
         result
     }
 
+    /// `wCurMap` in the saved main data.
+    pub fn current_map(&self) -> u8 {
+        self.data[0x260a]
+    }
+
+    /// `wLastMap` in the saved main data, the last outdoor map visited.
+    pub fn last_map(&self) -> u8 {
+        self.data[0x2611]
+    }
+
+    pub fn party(&self) -> PartyView<'_> {
+        PartyView::new(&self.data[0x2f2c..])
+    }
+
+    pub fn party_mut(&mut self) -> PartyViewMut<'_> {
+        PartyViewMut::new(&mut self.data[0x2f2c..])
+    }
+
     pub fn r#box(&self, id: BoxId) -> BoxView<'_> {
         BoxView::new(&self.data[id.sram_offset()..])
     }
@@ -0,0 +1,316 @@
+use crate::rom::ROM;
+
+use super::{PokeString, PokemonType};
+
+/// The move table, `Moves`, right before the base stats.
+const MOVES: usize = 0x038000;
+const MOVE_LENGTH: usize = 6;
+/// `MoveNames`, one name after the other, each ending in a terminator.
+const MOVE_NAMES: usize = 0x0b0000;
+const MOVE_NAME_LENGTH: usize = 13;
+
+/// Every move, numbered as in the game's move constants.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[repr(u8)]
+pub enum Move {
+    Pound = 1,
+    KarateChop = 2,
+    DoubleSlap = 3,
+    CometPunch = 4,
+    MegaPunch = 5,
+    PayDay = 6,
+    FirePunch = 7,
+    IcePunch = 8,
+    ThunderPunch = 9,
+    Scratch = 10,
+    ViceGrip = 11,
+    Guillotine = 12,
+    RazorWind = 13,
+    SwordsDance = 14,
+    Cut = 15,
+    Gust = 16,
+    WingAttack = 17,
+    Whirlwind = 18,
+    Fly = 19,
+    Bind = 20,
+    Slam = 21,
+    VineWhip = 22,
+    Stomp = 23,
+    DoubleKick = 24,
+    MegaKick = 25,
+    JumpKick = 26,
+    RollingKick = 27,
+    SandAttack = 28,
+    Headbutt = 29,
+    HornAttack = 30,
+    FuryAttack = 31,
+    HornDrill = 32,
+    Tackle = 33,
+    BodySlam = 34,
+    Wrap = 35,
+    TakeDown = 36,
+    Thrash = 37,
+    DoubleEdge = 38,
+    TailWhip = 39,
+    PoisonSting = 40,
+    Twineedle = 41,
+    PinMissile = 42,
+    Leer = 43,
+    Bite = 44,
+    Growl = 45,
+    Roar = 46,
+    Sing = 47,
+    Supersonic = 48,
+    SonicBoom = 49,
+    Disable = 50,
+    Acid = 51,
+    Ember = 52,
+    Flamethrower = 53,
+    Mist = 54,
+    WaterGun = 55,
+    HydroPump = 56,
+    Surf = 57,
+    IceBeam = 58,
+    Blizzard = 59,
+    Psybeam = 60,
+    BubbleBeam = 61,
+    AuroraBeam = 62,
+    HyperBeam = 63,
+    Peck = 64,
+    DrillPeck = 65,
+    Submission = 66,
+    LowKick = 67,
+    Counter = 68,
+    SeismicToss = 69,
+    Strength = 70,
+    Absorb = 71,
+    MegaDrain = 72,
+    LeechSeed = 73,
+    Growth = 74,
+    RazorLeaf = 75,
+    SolarBeam = 76,
+    PoisonPowder = 77,
+    StunSpore = 78,
+    SleepPowder = 79,
+    PetalDance = 80,
+    StringShot = 81,
+    DragonRage = 82,
+    FireSpin = 83,
+    ThunderShock = 84,
+    Thunderbolt = 85,
+    ThunderWave = 86,
+    Thunder = 87,
+    RockThrow = 88,
+    Earthquake = 89,
+    Fissure = 90,
+    Dig = 91,
+    Toxic = 92,
+    Confusion = 93,
+    Psychic = 94,
+    Hypnosis = 95,
+    Meditate = 96,
+    Agility = 97,
+    QuickAttack = 98,
+    Rage = 99,
+    Teleport = 100,
+    NightShade = 101,
+    Mimic = 102,
+    Screech = 103,
+    DoubleTeam = 104,
+    Recover = 105,
+    Harden = 106,
+    Minimize = 107,
+    Smokescreen = 108,
+    ConfuseRay = 109,
+    Withdraw = 110,
+    DefenseCurl = 111,
+    Barrier = 112,
+    LightScreen = 113,
+    Haze = 114,
+    Reflect = 115,
+    FocusEnergy = 116,
+    Bide = 117,
+    Metronome = 118,
+    MirrorMove = 119,
+    SelfDestruct = 120,
+    EggBomb = 121,
+    Lick = 122,
+    Smog = 123,
+    Sludge = 124,
+    BoneClub = 125,
+    FireBlast = 126,
+    Waterfall = 127,
+    Clamp = 128,
+    Swift = 129,
+    SkullBash = 130,
+    SpikeCannon = 131,
+    Constrict = 132,
+    Amnesia = 133,
+    Kinesis = 134,
+    SoftBoiled = 135,
+    HighJumpKick = 136,
+    Glare = 137,
+    DreamEater = 138,
+    PoisonGas = 139,
+    Barrage = 140,
+    LeechLife = 141,
+    LovelyKiss = 142,
+    SkyAttack = 143,
+    Transform = 144,
+    Bubble = 145,
+    DizzyPunch = 146,
+    Spore = 147,
+    Flash = 148,
+    Psywave = 149,
+    Splash = 150,
+    AcidArmor = 151,
+    Crabhammer = 152,
+    Explosion = 153,
+    FurySwipes = 154,
+    Bonemerang = 155,
+    Rest = 156,
+    RockSlide = 157,
+    HyperFang = 158,
+    Sharpen = 159,
+    Conversion = 160,
+    TriAttack = 161,
+    SuperFang = 162,
+    Slash = 163,
+    Substitute = 164,
+    Struggle = 165,
+}
+
+impl Move {
+    pub fn from_index(index: u8) -> Option<Move> {
+        match index {
+            1..=165 => Some(unsafe { std::mem::transmute::<u8, Move>(index) }),
+            _ => None,
+        }
+    }
+
+    pub fn into_index(self) -> u8 {
+        self as u8
+    }
+
+    fn data(self) -> &'static [u8] {
+        let start = MOVES + (self as usize - 1) * MOVE_LENGTH;
+        &ROM[start..start + MOVE_LENGTH]
+    }
+
+    pub fn name(self) -> PokeString {
+        let name = ROM[MOVE_NAMES..]
+            .split(|&byte| byte == 0x50)
+            .nth(self as usize - 1)
+            .unwrap_or_default();
+
+        PokeString::from_bytes(name, MOVE_NAME_LENGTH)
+    }
+
+    /// The effect constant, e.g. `POISON_SIDE_EFFECT1`, with 0 for moves that
+    /// only do damage.
+    pub fn effect(self) -> u8 {
+        self.data()[1]
+    }
+
+    /// Base power, with 0 for moves that don't do damage the usual way.
+    pub fn power(self) -> u8 {
+        self.data()[2]
+    }
+
+    pub fn move_type(self) -> PokemonType {
+        PokemonType::from_index(self.data()[3]).expect("Invalid move type in ROM")
+    }
+
+    /// Chance to hit, out of 255.
+    pub fn accuracy(self) -> u8 {
+        self.data()[4]
+    }
+
+    pub fn base_pp(self) -> u8 {
+        self.data()[5]
+    }
+
+    /// The most PP this move can have with `pp_ups` PP Ups used on it.
+    pub fn max_pp(self, pp_ups: u8) -> u8 {
+        pp_with_ups(self.base_pp(), pp_ups)
+    }
+}
+
+/// Each PP Up adds a fifth of the base PP, but at most 7, as in `AddBonusPP`.
+fn pp_with_ups(base_pp: u8, pp_ups: u8) -> u8 {
+    base_pp + u8::min(base_pp / 5, 7) * pp_ups
+}
+
+/// A move a Pokémon knows, decoded from its move id and PP byte, which holds
+/// the current PP and the PP Ups used in the top two bits.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct MoveSlot {
+    pub r#move: Move,
+    pub pp: u8,
+    pub pp_ups: u8,
+}
+
+impl MoveSlot {
+    /// `None` for an empty slot, or a move id that doesn't exist.
+    pub fn from_bytes(move_id: u8, pp: u8) -> Option<MoveSlot> {
+        Some(MoveSlot {
+            r#move: Move::from_index(move_id)?,
+            pp: pp & 0x3f,
+            pp_ups: pp >> 6,
+        })
+    }
+
+    /// The move id and PP byte, the inverse of [`MoveSlot::from_bytes`].
+    pub fn to_bytes(self) -> (u8, u8) {
+        (self.r#move.into_index(), (self.pp_ups << 6) | self.pp)
+    }
+
+    pub fn max_pp(self) -> u8 {
+        self.r#move.max_pp(self.pp_ups)
+    }
+}
+
+/// The moves in each slot of `moves` and `pp`, as stored in the party and in
+/// boxes.
+pub(super) fn move_slots(moves: [u8; 4], pp: [u8; 4]) -> [Option<MoveSlot>; 4] {
+    [0, 1, 2, 3].map(|slot| MoveSlot::from_bytes(moves[slot], pp[slot]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_index() {
+        assert_eq!(Move::from_index(0), None);
+        assert_eq!(Move::from_index(1), Some(Move::Pound));
+        assert_eq!(Move::from_index(94), Some(Move::Psychic));
+        assert_eq!(Move::from_index(165), Some(Move::Struggle));
+        assert_eq!(Move::from_index(166), None);
+    }
+
+    #[test]
+    fn test_move_slot_bytes() {
+        let slot = MoveSlot::from_bytes(85, (2 << 6) | 19).unwrap();
+        assert_eq!(
+            slot,
+            MoveSlot {
+                r#move: Move::Thunderbolt,
+                pp: 19,
+                pp_ups: 2,
+            }
+        );
+        assert_eq!(slot.to_bytes(), (85, (2 << 6) | 19));
+
+        assert_eq!(MoveSlot::from_bytes(0, 0), None);
+    }
+
+    #[test]
+    fn test_pp_with_ups() {
+        assert_eq!(pp_with_ups(35, 0), 35);
+        assert_eq!(pp_with_ups(35, 3), 56);
+        assert_eq!(pp_with_ups(40, 3), 61);
+        assert_eq!(pp_with_ups(5, 3), 8);
+        assert_eq!(pp_with_ups(1, 3), 1);
+    }
+}
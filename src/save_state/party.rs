@@ -1,6 +1,9 @@
 use crate::rom::ROM;
 
-use super::{BoxedPokemon, DeterminantValues, PokeString, PokemonSpecies};
+use super::{
+    moves, BoxedPokemon, DeterminantValues, MoveSlot, PokeString, PokemonSpecies, PokemonType,
+    StatusCondition,
+};
 
 trait PokemonSpeciesStats {
     fn base_hp(&self) -> u8;
@@ -8,11 +11,10 @@ trait PokemonSpeciesStats {
     fn base_defense(&self) -> u8;
     fn base_speed(&self) -> u8;
     fn base_special(&self) -> u8;
-    fn growth_rate(&self) -> u8;
 }
 
-const BASE_STATS: usize = 0x0383de;
-const BASE_DATA_SIZE: usize = 28;
+pub(super) const BASE_STATS: usize = 0x0383de;
+pub(super) const BASE_DATA_SIZE: usize = 28;
 
 impl PokemonSpeciesStats for PokemonSpecies {
     fn base_hp(&self) -> u8 {
@@ -34,10 +36,6 @@ impl PokemonSpeciesStats for PokemonSpecies {
     fn base_special(&self) -> u8 {
         ROM[BASE_STATS + (BASE_DATA_SIZE * (*self as usize - 1)) + 5]
     }
-
-    fn growth_rate(&self) -> u8 {
-        ROM[BASE_STATS + (BASE_DATA_SIZE * (*self as usize - 1)) + 19]
-    }
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
@@ -45,9 +43,9 @@ pub struct PartyPokemon {
     pub species: PokemonSpecies,
     pub hp: u16,
     pub box_level: u8,
-    pub status: u8,
-    pub type1: u8,
-    pub type2: u8,
+    pub status: StatusCondition,
+    pub type1: PokemonType,
+    pub type2: PokemonType,
     pub catch_rate: u8,
     pub moves: [u8; 4],
     pub ot_id: u16,
@@ -71,38 +69,19 @@ pub struct PartyPokemon {
 
 impl From<BoxedPokemon> for PartyPokemon {
     fn from(pokemon: BoxedPokemon) -> PartyPokemon {
-        let mut level = 1;
-        loop {
-            level += 1;
-
-            let exp_req = match pokemon.species.growth_rate() {
-                // GROWTH_MEDIUM_FAST
-                0 => level * level * level,
-                // GROWTH_SLIGHTLY_FAST
-                1 => unimplemented!(),
-                // GROWTH_SLIGHTLY_SLOW
-                2 => unimplemented!(),
-                // GROWTH_MEDIUM_SLOW
-                3 => (6 * level * level * level / 5) + (100 * level) - (15 * level * level) - 140,
-                // GROWTH_FAST
-                4 => 4 * level * level * level / 5,
-                // GROWTH_SLOW
-                5 => 5 * level * level * level / 4,
-                _ => unreachable!(),
-            };
-
-            if exp_req > pokemon.exp {
-                level -= 1;
-                break;
-            }
-
-            if level == 100 {
-                break;
-            }
-        }
+        let level = pokemon.species.growth_rate().level_for_exp(pokemon.exp);
+        PartyPokemon::with_level(pokemon, level)
+    }
+}
 
-        let level = level as u8;
+impl PartyPokemon {
+    pub fn move_slots(&self) -> [Option<MoveSlot>; 4] {
+        moves::move_slots(self.moves, self.pp)
+    }
 
+    /// Put `pokemon` in the party at `level`, working out its stats the same
+    /// way the game does.
+    pub fn with_level(pokemon: BoxedPokemon, level: u8) -> PartyPokemon {
         // HP: (((Base + IV) * 2 + ceil(Sqrt(stat exp)) / 4) * Level) / 100 + Level + 10
         let max_hp = (((pokemon.species.base_hp() as u16 + pokemon.dvs.hp() as u16) * 2
             + (pokemon.hp_exp as f32).sqrt().ceil() as u16 / 4)
@@ -204,9 +183,9 @@ impl PartyView<'_> {
             species,
             hp: u16::from_be_bytes([self.data[offset + 1], self.data[offset + 2]]),
             box_level: self.data[offset + 3],
-            status: self.data[offset + 4],
-            type1: self.data[offset + 5],
-            type2: self.data[offset + 6],
+            status: StatusCondition::from_bits(self.data[offset + 4]),
+            type1: PokemonType::from_byte(self.data[offset + 5]),
+            type2: PokemonType::from_byte(self.data[offset + 6]),
             catch_rate: self.data[offset + 7],
             moves: [
                 self.data[offset + 8],
@@ -247,6 +226,19 @@ impl PartyView<'_> {
     pub fn iter(&self) -> impl Iterator<Item = PartyPokemon> + '_ {
         PartyIter::new(self)
     }
+
+    /// Species and level of each Pokémon, without decoding the rest. Unlike
+    /// [`PartyView::get`], this skips anything invalid instead of panicking,
+    /// so it is safe to use on saves that may be corrupted.
+    pub fn species_and_levels(&self) -> Vec<(PokemonSpecies, u8)> {
+        (0..usize::min(self.len(), 6))
+            .filter_map(|index| {
+                let offset = 8 + (index * 44);
+                let species = PokemonSpecies::from_index(self.data[offset])?;
+                Some((species, self.data[offset + 33]))
+            })
+            .collect()
+    }
 }
 
 pub struct PartyViewMut<'a> {
@@ -274,9 +266,9 @@ impl PartyViewMut<'_> {
         self.data[9 + (index * 44)] = (pokemon.hp >> 8) as u8;
         self.data[10 + (index * 44)] = (pokemon.hp & 0xff) as u8;
         self.data[11 + (index * 44)] = pokemon.box_level;
-        self.data[12 + (index * 44)] = pokemon.status;
-        self.data[13 + (index * 44)] = pokemon.type1;
-        self.data[14 + (index * 44)] = pokemon.type2;
+        self.data[12 + (index * 44)] = pokemon.status.bits();
+        self.data[13 + (index * 44)] = pokemon.type1.into_index();
+        self.data[14 + (index * 44)] = pokemon.type2.into_index();
         self.data[15 + (index * 44)] = pokemon.catch_rate;
         self.data[16 + (index * 44)] = pokemon.moves[0];
         self.data[17 + (index * 44)] = pokemon.moves[1];
@@ -343,11 +335,17 @@ impl PartyViewMut<'_> {
         self.data[348 + (index * 11)] = name_bytes.next().unwrap_or(0x50);
     }
 
+    pub fn clear(&mut self) {
+        self.data[0] = 0;
+        self.data[1] = 0xff;
+    }
+
     pub fn push(&mut self, pokemon: PartyPokemon) {
         assert!(self.data[0] < 6);
         let index = self.data[0] as usize;
         self.data[0] += 1;
         self.set(index, pokemon);
+        self.data[1 + index + 1] = 0xff;
     }
 
     pub fn remove(&mut self, index: usize) -> PartyPokemon {
@@ -403,9 +401,9 @@ mod test {
                 species: PokemonSpecies::Haunter,
                 hp: 0,
                 level: 33,
-                status: 0,
-                type1: 8,
-                type2: 3,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Ghost,
+                type2: PokemonType::Poison,
                 catch_rate: 90,
                 moves: [92, 95, 101, 102],
                 ot_id: 35244,
@@ -424,9 +422,9 @@ mod test {
                 species: PokemonSpecies::Haunter,
                 hp: 0,
                 box_level: 33,
-                status: 0,
-                type1: 8,
-                type2: 3,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Ghost,
+                type2: PokemonType::Poison,
                 catch_rate: 90,
                 moves: [92, 95, 101, 102],
                 ot_id: 35244,
@@ -454,9 +452,9 @@ mod test {
                 species: PokemonSpecies::Venonat,
                 hp: 0,
                 level: 17,
-                status: 0,
-                type1: 7,
-                type2: 3,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Bug,
+                type2: PokemonType::Poison,
                 catch_rate: 190,
                 moves: [33, 50, 48, 0],
                 ot_id: 35244,
@@ -475,9 +473,9 @@ mod test {
                 species: PokemonSpecies::Venonat,
                 hp: 0,
                 box_level: 17,
-                status: 0,
-                type1: 7,
-                type2: 3,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Bug,
+                type2: PokemonType::Poison,
                 catch_rate: 190,
                 moves: [33, 50, 48, 0],
                 ot_id: 35244,
@@ -505,9 +503,9 @@ mod test {
                 species: PokemonSpecies::Kadabra,
                 hp: 0,
                 level: 49,
-                status: 0,
-                type1: 24,
-                type2: 24,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Psychic,
+                type2: PokemonType::Psychic,
                 catch_rate: 200,
                 moves: [94, 148, 25, 60],
                 ot_id: 35244,
@@ -526,9 +524,9 @@ mod test {
                 species: PokemonSpecies::Kadabra,
                 hp: 0,
                 box_level: 49,
-                status: 0,
-                type1: 24,
-                type2: 24,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Psychic,
+                type2: PokemonType::Psychic,
                 catch_rate: 200,
                 moves: [94, 148, 25, 60],
                 ot_id: 35244,
@@ -556,9 +554,9 @@ mod test {
                 species: PokemonSpecies::Nidoqueen,
                 hp: 156,
                 level: 0,
-                status: 0,
-                type1: 3,
-                type2: 4,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Poison,
+                type2: PokemonType::Ground,
                 catch_rate: 235,
                 moves: [57, 34, 38, 70],
                 ot_id: 35244,
@@ -577,9 +575,9 @@ mod test {
                 species: PokemonSpecies::Nidoqueen,
                 hp: 156,
                 box_level: 0,
-                status: 0,
-                type1: 3,
-                type2: 4,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Poison,
+                type2: PokemonType::Ground,
                 catch_rate: 235,
                 moves: [57, 34, 38, 70],
                 ot_id: 35244,
@@ -607,9 +605,9 @@ mod test {
                 species: PokemonSpecies::Charizard,
                 hp: 92,
                 level: 25,
-                status: 0,
-                type1: 20,
-                type2: 2,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Fire,
+                type2: PokemonType::Flying,
                 catch_rate: 45,
                 moves: [130, 15, 53, 19],
                 ot_id: 35244,
@@ -628,9 +626,9 @@ mod test {
                 species: PokemonSpecies::Charizard,
                 hp: 92,
                 box_level: 25,
-                status: 0,
-                type1: 20,
-                type2: 2,
+                status: StatusCondition::NONE,
+                type1: PokemonType::Fire,
+                type2: PokemonType::Flying,
                 catch_rate: 45,
                 moves: [130, 15, 53, 19],
                 ot_id: 35244,
@@ -653,4 +651,24 @@ mod test {
             },
         );
     }
+
+    #[test]
+    fn test_unknown_type() {
+        let mut data = vec![0x50; 404];
+        data[0] = 1;
+        data[1] = PokemonSpecies::Pikachu.into_index();
+        data[2] = 0xff;
+        data[8..52].fill(0);
+        data[8] = PokemonSpecies::Pikachu.into_index();
+        data[13] = 0x09;
+        data[14] = PokemonType::Electric.into_index();
+
+        let pokemon = PartyView::new(&data).get(0).unwrap();
+        assert_eq!(pokemon.type1, PokemonType::Unknown(0x09));
+        assert_eq!(pokemon.type2, PokemonType::Electric);
+
+        let before = data.clone();
+        PartyViewMut::new(&mut data).set(0, pokemon);
+        assert_eq!(data, before);
+    }
 }
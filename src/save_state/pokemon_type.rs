@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rom::ROM;
+
+use super::{
+    party::{BASE_DATA_SIZE, BASE_STATS},
+    PokemonSpecies,
+};
+
+/// A type, numbered as in the game's type constants. The physical types come
+/// first, and the special types start at [`PokemonType::Fire`].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PokemonType {
+    Normal,
+    Fighting,
+    Flying,
+    Poison,
+    Ground,
+    Rock,
+    /// Unused, except by glitch Pokémon.
+    Bird,
+    Bug,
+    Ghost,
+    Fire,
+    Water,
+    Grass,
+    Electric,
+    Psychic,
+    Ice,
+    Dragon,
+    /// A byte that isn't one of the types above, kept as it is. Glitch
+    /// Pokémon and damaged saves can have these.
+    Unknown(u8),
+}
+
+impl PokemonType {
+    /// The type numbered `index`, or `None` if the game doesn't define one.
+    pub fn from_index(index: u8) -> Option<PokemonType> {
+        match PokemonType::from_byte(index) {
+            PokemonType::Unknown(_) => None,
+            known => Some(known),
+        }
+    }
+
+    /// The type numbered `byte`, keeping bytes that aren't a type as
+    /// [`PokemonType::Unknown`] so they can be written back unchanged.
+    pub fn from_byte(byte: u8) -> PokemonType {
+        match byte {
+            0x00 => PokemonType::Normal,
+            0x01 => PokemonType::Fighting,
+            0x02 => PokemonType::Flying,
+            0x03 => PokemonType::Poison,
+            0x04 => PokemonType::Ground,
+            0x05 => PokemonType::Rock,
+            0x06 => PokemonType::Bird,
+            0x07 => PokemonType::Bug,
+            0x08 => PokemonType::Ghost,
+            0x14 => PokemonType::Fire,
+            0x15 => PokemonType::Water,
+            0x16 => PokemonType::Grass,
+            0x17 => PokemonType::Electric,
+            0x18 => PokemonType::Psychic,
+            0x19 => PokemonType::Ice,
+            0x1a => PokemonType::Dragon,
+            _ => PokemonType::Unknown(byte),
+        }
+    }
+
+    pub fn into_index(self) -> u8 {
+        match self {
+            PokemonType::Normal => 0x00,
+            PokemonType::Fighting => 0x01,
+            PokemonType::Flying => 0x02,
+            PokemonType::Poison => 0x03,
+            PokemonType::Ground => 0x04,
+            PokemonType::Rock => 0x05,
+            PokemonType::Bird => 0x06,
+            PokemonType::Bug => 0x07,
+            PokemonType::Ghost => 0x08,
+            PokemonType::Fire => 0x14,
+            PokemonType::Water => 0x15,
+            PokemonType::Grass => 0x16,
+            PokemonType::Electric => 0x17,
+            PokemonType::Psychic => 0x18,
+            PokemonType::Ice => 0x19,
+            PokemonType::Dragon => 0x1a,
+            PokemonType::Unknown(byte) => byte,
+        }
+    }
+
+    /// Whether moves of this type use Special instead of Attack and Defense.
+    pub fn is_special(self) -> bool {
+        self.into_index() >= PokemonType::Fire.into_index()
+    }
+
+    /// How effective a move of this type is against a Pokémon of type
+    /// `defender`.
+    pub fn effectiveness(self, defender: PokemonType) -> Effectiveness {
+        TYPE_EFFECTS
+            .iter()
+            .find(|&&(attacker, def, _)| attacker == self && def == defender)
+            .map_or(Effectiveness::Effective, |&(_, _, effectiveness)| {
+                effectiveness
+            })
+    }
+
+    /// How much damage a move of this type does against a Pokémon with
+    /// `defender` types, in percent, as in `AdjustDamageForMoveType`. A
+    /// Pokémon with a single type has it twice, which only counts once.
+    pub fn damage_percent(self, defender: [PokemonType; 2]) -> u32 {
+        TYPE_EFFECTS
+            .iter()
+            .filter(|&&(attacker, def, _)| attacker == self && defender.contains(&def))
+            .fold(100, |percent, &(_, _, effectiveness)| {
+                percent * effectiveness as u32 / 10
+            })
+    }
+}
+
+impl PokemonSpecies {
+    /// The types from the species' base stats. Species with a single type
+    /// have it twice.
+    pub fn types(self) -> [PokemonType; 2] {
+        let start = BASE_STATS + (BASE_DATA_SIZE * (self as usize - 1)) + 6;
+        [ROM[start], ROM[start + 1]]
+            .map(|index| PokemonType::from_index(index).expect("Invalid type in ROM"))
+    }
+}
+
+/// A damage multiplier from the type chart, in tenths.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[repr(u8)]
+pub enum Effectiveness {
+    NoEffect = 0,
+    NotVeryEffective = 5,
+    Effective = 10,
+    SuperEffective = 20,
+}
+
+/// The type chart, `TypeEffects`, in the same order. Pairs that aren't listed
+/// are [`Effectiveness::Effective`]. The tests check it against the ROM.
+#[rustfmt::skip]
+pub const TYPE_EFFECTS: &[(PokemonType, PokemonType, Effectiveness)] = {
+    use Effectiveness::*;
+    use PokemonType::*;
+
+    &[
+        (Water, Fire, SuperEffective),
+        (Fire, Grass, SuperEffective),
+        (Fire, Ice, SuperEffective),
+        (Grass, Water, SuperEffective),
+        (Electric, Water, SuperEffective),
+        (Water, Rock, SuperEffective),
+        (Ground, Flying, NoEffect),
+        (Water, Water, NotVeryEffective),
+        (Fire, Fire, NotVeryEffective),
+        (Electric, Electric, NotVeryEffective),
+        (Ice, Ice, NotVeryEffective),
+        (Grass, Grass, NotVeryEffective),
+        (Psychic, Psychic, NotVeryEffective),
+        (Fire, Water, NotVeryEffective),
+        (Grass, Fire, NotVeryEffective),
+        (Water, Grass, NotVeryEffective),
+        (Electric, Grass, NotVeryEffective),
+        (Normal, Rock, NotVeryEffective),
+        (Normal, Ghost, NoEffect),
+        (Ghost, Ghost, SuperEffective),
+        (Fire, Bug, SuperEffective),
+        (Fire, Rock, NotVeryEffective),
+        (Water, Ground, SuperEffective),
+        (Electric, Ground, NoEffect),
+        (Electric, Flying, SuperEffective),
+        (Grass, Ground, SuperEffective),
+        (Grass, Bug, NotVeryEffective),
+        (Grass, Poison, NotVeryEffective),
+        (Grass, Rock, SuperEffective),
+        (Grass, Flying, NotVeryEffective),
+        (Ice, Water, NotVeryEffective),
+        (Ice, Grass, SuperEffective),
+        (Ice, Ground, SuperEffective),
+        (Ice, Flying, SuperEffective),
+        (Fighting, Normal, SuperEffective),
+        (Fighting, Poison, NotVeryEffective),
+        (Fighting, Flying, NotVeryEffective),
+        (Fighting, Psychic, NotVeryEffective),
+        (Fighting, Bug, NotVeryEffective),
+        (Fighting, Rock, SuperEffective),
+        (Fighting, Ice, SuperEffective),
+        (Fighting, Ghost, NoEffect),
+        (Poison, Grass, SuperEffective),
+        (Poison, Poison, NotVeryEffective),
+        (Poison, Ground, NotVeryEffective),
+        (Poison, Bug, SuperEffective),
+        (Poison, Rock, NotVeryEffective),
+        (Poison, Ghost, NotVeryEffective),
+        (Ground, Fire, SuperEffective),
+        (Ground, Electric, SuperEffective),
+        (Ground, Grass, NotVeryEffective),
+        (Ground, Bug, NotVeryEffective),
+        (Ground, Rock, SuperEffective),
+        (Ground, Poison, SuperEffective),
+        (Flying, Electric, NotVeryEffective),
+        (Flying, Fighting, SuperEffective),
+        (Flying, Bug, SuperEffective),
+        (Flying, Grass, SuperEffective),
+        (Flying, Rock, NotVeryEffective),
+        (Psychic, Fighting, SuperEffective),
+        (Psychic, Poison, SuperEffective),
+        (Bug, Fire, NotVeryEffective),
+        (Bug, Grass, SuperEffective),
+        (Bug, Fighting, NotVeryEffective),
+        (Bug, Flying, NotVeryEffective),
+        (Bug, Psychic, SuperEffective),
+        (Bug, Ghost, NotVeryEffective),
+        (Bug, Poison, SuperEffective),
+        (Rock, Fire, SuperEffective),
+        (Rock, Fighting, NotVeryEffective),
+        (Rock, Ground, NotVeryEffective),
+        (Rock, Flying, SuperEffective),
+        (Rock, Bug, SuperEffective),
+        (Rock, Ice, SuperEffective),
+        (Ghost, Normal, NoEffect),
+        (Ghost, Psychic, NoEffect),
+        (Fire, Dragon, NotVeryEffective),
+        (Water, Dragon, NotVeryEffective),
+        (Electric, Dragon, NotVeryEffective),
+        (Grass, Dragon, NotVeryEffective),
+        (Ice, Dragon, SuperEffective),
+        (Dragon, Dragon, SuperEffective),
+    ]
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_index() {
+        assert_eq!(PokemonType::from_index(0x08), Some(PokemonType::Ghost));
+        assert_eq!(PokemonType::from_index(0x09), None);
+        assert_eq!(PokemonType::from_index(0x13), None);
+        assert_eq!(PokemonType::from_index(0x14), Some(PokemonType::Fire));
+        assert_eq!(PokemonType::from_index(0x1a), Some(PokemonType::Dragon));
+        assert_eq!(PokemonType::from_index(0x1b), None);
+
+        assert_eq!(PokemonType::from_byte(0x09), PokemonType::Unknown(0x09));
+        assert_eq!(PokemonType::from_byte(0x09).into_index(), 0x09);
+        assert_eq!(PokemonType::from_byte(0x1a), PokemonType::Dragon);
+
+        assert!(!PokemonType::Ghost.is_special());
+        assert!(PokemonType::Fire.is_special());
+    }
+
+    #[test]
+    fn test_species_types() {
+        use PokemonType::*;
+
+        assert_eq!(PokemonSpecies::Pikachu.types(), [Electric, Electric]);
+        assert_eq!(PokemonSpecies::Charizard.types(), [Fire, Flying]);
+        assert_eq!(PokemonSpecies::Gengar.types(), [Ghost, Poison]);
+    }
+
+    #[test]
+    fn test_type_chart() {
+        use PokemonType::*;
+
+        // Gen 1 quirks: Ghost doesn't affect Psychic, and Bug hits Poison
+        assert_eq!(Ghost.effectiveness(Psychic), Effectiveness::NoEffect);
+        assert_eq!(Bug.effectiveness(Poison), Effectiveness::SuperEffective);
+        assert_eq!(Normal.effectiveness(Water), Effectiveness::Effective);
+
+        assert_eq!(Electric.damage_percent([Water, Flying]), 400);
+        assert_eq!(Fire.damage_percent([Water, Water]), 50);
+        assert_eq!(Ground.damage_percent([Rock, Flying]), 0);
+        assert_eq!(Ice.damage_percent([Water, Psychic]), 50);
+        assert_eq!(Normal.damage_percent([Normal, Normal]), 100);
+    }
+
+    #[test]
+    fn test_type_chart_matches_rom() {
+        // Attacker, defender and multiplier for each entry, then a terminator
+        let expected = TYPE_EFFECTS
+            .iter()
+            .flat_map(|&(attacker, defender, effectiveness)| {
+                [
+                    attacker.into_index(),
+                    defender.into_index(),
+                    effectiveness as u8,
+                ]
+            })
+            .chain([0xff])
+            .collect::<Vec<_>>();
+
+        let found = ROM
+            .windows(expected.len())
+            .filter(|window| *window == expected)
+            .count();
+
+        assert_eq!(
+            found, 1,
+            "TypeEffects in the ROM doesn't match TYPE_EFFECTS"
+        );
+    }
+
+    #[test]
+    fn test_type_chart_has_no_duplicates() {
+        for (idx, &(attacker, defender, _)) in TYPE_EFFECTS.iter().enumerate() {
+            assert!(!TYPE_EFFECTS[idx + 1..]
+                .iter()
+                .any(|&(a, d, _)| (a, d) == (attacker, defender)));
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::ops::BitOr;
+
+use serde::{Deserialize, Serialize};
+
+const SLEEP_MASK: u8 = 0b0000_0111;
+/// The bits that are used at all.
+const STATUS_MASK: u8 = 0b0111_1111;
+
+/// The status condition of a Pokémon, as stored in the party and in boxes:
+/// the number of turns left asleep in the low three bits, then one bit each
+/// for poison, burn, freeze and paralysis.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StatusCondition(u8);
+
+impl StatusCondition {
+    pub const NONE: StatusCondition = StatusCondition(0);
+    pub const POISON: StatusCondition = StatusCondition(1 << 3);
+    pub const BURN: StatusCondition = StatusCondition(1 << 4);
+    pub const FREEZE: StatusCondition = StatusCondition(1 << 5);
+    pub const PARALYSIS: StatusCondition = StatusCondition(1 << 6);
+
+    pub fn from_bits(bits: u8) -> StatusCondition {
+        StatusCondition(bits)
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Asleep for `turns` more turns, from 1 to 7.
+    pub fn asleep(turns: u8) -> StatusCondition {
+        assert!((1..=SLEEP_MASK).contains(&turns));
+        StatusCondition(turns)
+    }
+
+    /// The number of turns left asleep, or 0 if awake.
+    pub fn sleep_turns(self) -> u8 {
+        self.0 & SLEEP_MASK
+    }
+
+    pub fn contains(self, other: StatusCondition) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_healthy(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether the game could have set this, with no unused bits and at most
+    /// one condition at a time.
+    pub fn is_valid(self) -> bool {
+        let conditions = (self.0 >> 3).count_ones() + (self.sleep_turns() != 0) as u32;
+        self.0 & !STATUS_MASK == 0 && conditions <= 1
+    }
+}
+
+impl BitOr for StatusCondition {
+    type Output = StatusCondition;
+
+    fn bitor(self, rhs: StatusCondition) -> StatusCondition {
+        StatusCondition(self.0 | rhs.0)
+    }
+}
+
+impl std::fmt::Debug for StatusCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StatusCondition")
+            .field("sleep_turns", &self.sleep_turns())
+            .field("poison", &self.contains(StatusCondition::POISON))
+            .field("burn", &self.contains(StatusCondition::BURN))
+            .field("freeze", &self.contains(StatusCondition::FREEZE))
+            .field("paralysis", &self.contains(StatusCondition::PARALYSIS))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_status_condition() {
+        assert!(StatusCondition::NONE.is_healthy());
+        assert!(StatusCondition::NONE.is_valid());
+
+        let asleep = StatusCondition::asleep(3);
+        assert_eq!(asleep.sleep_turns(), 3);
+        assert!(asleep.is_valid());
+
+        assert!(StatusCondition::from_bits(0x40).contains(StatusCondition::PARALYSIS));
+        assert!(!(StatusCondition::POISON | StatusCondition::BURN).is_valid());
+        assert!(!(StatusCondition::asleep(1) | StatusCondition::FREEZE).is_valid());
+        assert!(!StatusCondition::from_bits(0x80).is_valid());
+    }
+}
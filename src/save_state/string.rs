@@ -1,8 +1,64 @@
 use std::fmt::{Debug, Display, Write};
 
+use super::charmap;
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct PokeString(Vec<u8>);
 
+/// The names stored in a save, which limit how long they can be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameField {
+    /// The player's and rival's names, and the OT name of a Pokémon.
+    Player,
+    /// The nickname of a Pokémon in the party or a box.
+    Nickname,
+    /// The name of a PC box. Yellow only numbers its boxes, so this has the
+    /// limit of the box naming screen in Gold, Silver and Crystal.
+    Box,
+}
+
+impl NameField {
+    /// The most bytes the naming screen allows, without the terminator.
+    pub fn max_len(self) -> usize {
+        match self {
+            NameField::Player => 7,
+            NameField::Nickname => 10,
+            NameField::Box => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The character at this byte offset isn't in the charmap.
+    Unrepresentable {
+        chr: char,
+        offset: usize,
+    },
+    /// The name needs more bytes than its field fits.
+    TooLong {
+        len: usize,
+        max_len: usize,
+    },
+    Empty,
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::Unrepresentable { chr, offset } => {
+                write!(f, "can't write {:?} at offset {}", chr, offset)
+            }
+            EncodeError::TooLong { len, max_len } => {
+                write!(f, "{} characters, at most {} fit", len, max_len)
+            }
+            EncodeError::Empty => write!(f, "names can't be empty"),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
 impl PokeString {
     pub fn from_bytes(data: &[u8], max_len: usize) -> PokeString {
         let result = data
@@ -15,6 +71,40 @@ impl PokeString {
         PokeString(result)
     }
 
+    /// The inverse of [`Display`], so that `encode(s)?.to_string() == s`.
+    /// Each character is encoded on its own, the same as on the naming
+    /// screen, so text such as `POKé` or `'s` comes out as separate letters
+    /// instead of the ligature, which looks the same.
+    pub fn encode(text: &str) -> Result<PokeString, EncodeError> {
+        text.char_indices()
+            .map(|(offset, chr)| {
+                charmap::lookup_char(chr)
+                    .map(|entry| entry.byte)
+                    .ok_or(EncodeError::Unrepresentable { chr, offset })
+            })
+            .collect::<Result<_, _>>()
+            .map(PokeString)
+    }
+
+    /// Encode a name to store in `field`, which has to fit and can't be
+    /// empty.
+    pub fn encode_name(text: &str, field: NameField) -> Result<PokeString, EncodeError> {
+        let result = PokeString::encode(text)?;
+
+        if result.is_empty() {
+            return Err(EncodeError::Empty);
+        }
+
+        if result.len() > field.max_len() {
+            return Err(EncodeError::TooLong {
+                len: result.len(),
+                max_len: field.max_len(),
+            });
+        }
+
+        Ok(result)
+    }
+
     pub fn iter(&self) -> std::iter::Copied<std::slice::Iter<'_, u8>> {
         self.into_iter()
     }
@@ -22,6 +112,10 @@ impl PokeString {
     pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 impl<'a> IntoIterator for &'a PokeString {
@@ -35,79 +129,10 @@ impl<'a> IntoIterator for &'a PokeString {
 
 impl Display for PokeString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for ch in &self.0 {
-            match ch {
-                0x4a => f.write_str("𝔭𝔪")?,
-                0x50 => unreachable!(),
-                0x54 => f.write_str("POKé")?,
-                0x56 => f.write_str("……")?,
-                0x5b => f.write_str("PC")?,
-                0x5c => f.write_str("TM")?,
-                0x5d => f.write_str("TRAINER")?,
-                0x5e => f.write_str("ROCKET")?,
-
-                0x69 => f.write_char('𝐕')?,
-                0x6a => f.write_char('𝐒')?,
-                0x6d => f.write_char(':')?,
-                0x70 => f.write_char('‘')?,
-                0x71 => f.write_char('’')?,
-                0x72 => f.write_char('“')?,
-                0x73 => f.write_char('”')?,
-                0x74 => f.write_char('·')?,
-                0x75 => f.write_char('…')?,
-
-                0x79 => f.write_char('┌')?,
-                0x7a => f.write_char('─')?,
-                0x7b => f.write_char('┐')?,
-                0x7c => f.write_char('│')?,
-                0x7d => f.write_char('└')?,
-                0x7e => f.write_char('┘')?,
-                0x7f => f.write_char(' ')?,
-
-                0x80..=0x99 => f.write_char((b'A' + (ch - 0x80)) as char)?,
-
-                0x9a => f.write_char('(')?,
-                0x9b => f.write_char(')')?,
-                0x9c => f.write_char(':')?,
-                0x9d => f.write_char(';')?,
-                0x9e => f.write_char('[')?,
-                0x9f => f.write_char(']')?,
-
-                0xa0..=0xb9 => f.write_char((b'a' + (ch - 0xa0)) as char)?,
-
-                0xba => f.write_char('é')?,
-                0xbb => f.write_str("'d")?,
-                0xbc => f.write_str("'l")?,
-                0xbd => f.write_str("'s")?,
-                0xbe => f.write_str("'t")?,
-                0xbf => f.write_str("'v")?,
-
-                0xe0 => f.write_char('\'')?,
-                0xe1 => f.write_char('𝔭')?,
-                0xe2 => f.write_char('𝔪')?,
-                0xe3 => f.write_char('-')?,
-
-                0xe4 => f.write_str("'r")?,
-                0xe5 => f.write_str("'m")?,
-
-                0xe6 => f.write_char('?')?,
-                0xe7 => f.write_char('!')?,
-                0xe8 => f.write_char('.')?,
-
-                0xec => f.write_char('▷')?,
-                0xed => f.write_char('▶')?,
-                0xee => f.write_char('▼')?,
-                0xef => f.write_char('♂')?,
-                0xf0 => f.write_char('¥')?,
-                0xf1 => f.write_char('×')?,
-                0xf2 => f.write_char('.')?,
-                0xf3 => f.write_char('/')?,
-                0xf4 => f.write_char(',')?,
-                0xf5 => f.write_char('♀')?,
-
-                0xf6..=0xff => f.write_char((b'0' + (ch - 0xf6)) as char)?,
-
-                _ => f.write_char('�')?,
+        for &byte in &self.0 {
+            match charmap::lookup_byte(byte) {
+                Some(entry) => f.write_str(entry.text)?,
+                None => f.write_char(charmap::REPLACEMENT_CHAR)?,
             }
         }
 
@@ -120,3 +145,89 @@ impl Debug for PokeString {
         write!(f, "PokeString {:?}", format!("{}", self))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::save_state::PokemonSpecies;
+
+    #[test]
+    fn test_encode_ligatures() {
+        let encoded = PokeString::encode("POKé's PC").unwrap();
+        assert_eq!(
+            encoded.iter().collect::<Vec<_>>(),
+            vec![0x8f, 0x8e, 0x8a, 0xba, 0xe0, 0xb2, 0x7f, 0x8f, 0x82]
+        );
+
+        let decoded = PokeString::from_bytes(&[0x54, 0xbd, 0x7f, 0x5b, 0xf5], 5).to_string();
+        assert_eq!(decoded, "POKé's PC♀");
+        assert_eq!(PokeString::encode(&decoded).unwrap().to_string(), decoded);
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        assert_eq!(
+            PokeString::encode("AB~"),
+            Err(EncodeError::Unrepresentable {
+                chr: '~',
+                offset: 2
+            })
+        );
+        assert_eq!(
+            PokeString::encode_name("PIKACHU", NameField::Player).map(|name| name.len()),
+            Ok(7)
+        );
+        assert_eq!(
+            PokeString::encode_name("SQUIRTLE", NameField::Player),
+            Err(EncodeError::TooLong { len: 8, max_len: 7 })
+        );
+        assert_eq!(
+            PokeString::encode_name("", NameField::Nickname),
+            Err(EncodeError::Empty)
+        );
+        assert_eq!(
+            PokeString::encode_name("FAVORITES", NameField::Box),
+            Err(EncodeError::TooLong { len: 9, max_len: 8 })
+        );
+    }
+
+    #[test]
+    fn test_encode_species_names() {
+        for species in (0..=u8::MAX).filter_map(PokemonSpecies::from_index) {
+            let name = species.name();
+            assert_eq!(PokeString::encode(&name.to_string()), Ok(name));
+        }
+    }
+
+    fn charmap_text() -> impl Strategy<Value = String> {
+        let texts = charmap::CHARMAP
+            .iter()
+            .map(|entry| entry.text)
+            .collect::<Vec<_>>();
+        prop::collection::vec(prop::sample::select(texts), 0..20).prop_map(|texts| texts.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn test_encode_round_trip(text in charmap_text()) {
+            prop_assert_eq!(PokeString::encode(&text).unwrap().to_string(), text);
+        }
+
+        #[test]
+        fn test_decode_round_trip(bytes in prop::collection::vec(prop::sample::select(
+            charmap::CHARMAP.iter().map(|entry| entry.byte).collect::<Vec<_>>()
+        ), 0..20)) {
+            let decoded = PokeString(bytes).to_string();
+            prop_assert_eq!(PokeString::encode(&decoded).unwrap().to_string(), decoded);
+        }
+
+        #[test]
+        fn test_encode_never_panics(text in ".*") {
+            if let Ok(encoded) = PokeString::encode(&text) {
+                prop_assert_eq!(encoded.to_string(), text);
+            }
+        }
+    }
+}
@@ -1,22 +1,90 @@
-// Just do what Factorio & Prison Architect do:
+// Just do what Factorio & Prison Architect do on macOS and Windows:
 // https://savelocation.net/factorio
 // https://savelocation.net/prison-architect
+// and follow the XDG Base Directory specification on Linux.
 
-use std::{env, ffi::OsString, fs, io::Result, path::PathBuf};
+use std::{
+    ffi::OsString,
+    fs,
+    io::Result,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config,
+    save_metadata::{get_metadata_path, get_thumbnail_path, refresh_metadata},
+    save_state::{SaveDocument, SaveState},
+    settings::settings,
+};
 
 #[cfg(target_os = "macos")]
-fn get_save_dir() -> PathBuf {
-    PathBuf::from(env::var("HOME").unwrap()).join("Library/Application Support/Rustic Yellow/saves")
+fn get_platform_data_dir() -> Result<PathBuf> {
+    Ok(config::env_dir("HOME")?.join("Library/Application Support/Rustic Yellow"))
 }
 
 #[cfg(target_os = "linux")]
-fn get_save_dir() -> PathBuf {
-    PathBuf::from(env::var("HOME").unwrap()).join(".Rustic Yellow/saves")
+fn get_platform_data_dir() -> Result<PathBuf> {
+    let dir = match config::env_dir("XDG_DATA_HOME") {
+        Ok(dir) => dir.join("rustic-yellow"),
+        Err(_) => config::env_dir("HOME")?.join(".local/share/rustic-yellow"),
+    };
+
+    // Older versions kept everything in ~/.Rustic Yellow, so without a home
+    // directory there's nothing to move
+    let legacy_dir = match config::env_dir("HOME") {
+        Ok(home) => home.join(".Rustic Yellow"),
+        Err(_) => return Ok(dir),
+    };
+
+    static MIGRATE: std::sync::Once = std::sync::Once::new();
+    MIGRATE.call_once(|| migrate_data_dir(&legacy_dir, &dir));
+
+    // Keep using the old directory if it couldn't be moved
+    if legacy_dir.exists() && !dir.exists() {
+        return Ok(legacy_dir);
+    }
+
+    Ok(dir)
 }
 
 #[cfg(target_os = "windows")]
-fn get_save_dir() -> PathBuf {
-    PathBuf::from(env::var("appdata").unwrap()).join("Rustic Yellow\\saves")
+fn get_platform_data_dir() -> Result<PathBuf> {
+    Ok(config::env_dir("appdata")?.join("Rustic Yellow"))
+}
+
+/// Move the data directory from where an older version kept it, if there's
+/// nothing in the new location yet.
+#[cfg(target_os = "linux")]
+fn migrate_data_dir(from: &Path, to: &Path) {
+    if !from.is_dir() || to.exists() {
+        return;
+    }
+
+    let result = match to.parent() {
+        Some(parent) => fs::create_dir_all(parent).and_then(|()| fs::rename(from, to)),
+        None => fs::rename(from, to),
+    };
+
+    match result {
+        Ok(()) => log::info!("Moved {} to {}", from.display(), to.display()),
+        Err(e) => log::error!("Error moving {} to {}: {}", from.display(), to.display(), e),
+    }
+}
+
+/// Where saves, screenshots and crash reports are kept: next to the
+/// executable in portable mode, or in the platform's data directory.
+pub fn get_data_dir() -> Result<PathBuf> {
+    match config::portable_dir() {
+        Some(dir) => Ok(dir),
+        None => get_platform_data_dir(),
+    }
+}
+
+fn get_save_dir() -> Result<PathBuf> {
+    match &settings().save_dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Ok(get_data_dir()?.join("saves")),
+    }
 }
 
 pub struct SaveFile {
@@ -25,22 +93,37 @@ pub struct SaveFile {
 }
 
 pub fn create_save_dir() -> Result<()> {
-    fs::create_dir_all(get_save_dir())
+    fs::create_dir_all(get_save_dir()?)
 }
 
-pub fn get_save_path(name: &str) -> PathBuf {
-    let ext: OsString = OsString::from("sav");
-    get_save_dir().join(name).with_extension(ext)
+pub fn get_save_path(name: &str) -> Result<PathBuf> {
+    Ok(save_path_in(&get_save_dir()?, name))
+}
+
+fn save_path_in(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.sav", name))
+}
+
+/// Longest save name that fits in the menus.
+pub const MAX_SAVE_NAME_LEN: usize = 14;
+
+/// Whether `name` can be used as the name of a save, and fits in the menus.
+/// Dots aren't allowed, since the files that belong to a save are told apart
+/// by their extensions.
+pub fn is_valid_save_name(name: &str) -> bool {
+    let len = name.chars().count();
+
+    (1..=MAX_SAVE_NAME_LEN).contains(&len) && !name.contains(['.', '/', '\\', ':'])
 }
 
 pub fn save_is_free(name: &str) -> bool {
-    !get_save_path(name).exists()
+    get_save_dir().is_ok_and(|dir| check_new_name(&dir, name).is_ok())
 }
 
 pub fn list_save_files() -> Result<Vec<SaveFile>> {
     let ext: OsString = OsString::from("sav");
 
-    let dir = match fs::read_dir(get_save_dir()) {
+    let dir = match fs::read_dir(get_save_dir()?) {
         Ok(dir) => dir,
         Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
         Err(e) => return Err(e),
@@ -75,3 +158,501 @@ pub fn list_save_files() -> Result<Vec<SaveFile>> {
 
     Ok(files)
 }
+
+/// Where saves from other emulators or cartridge dumps can be put, to be
+/// imported from the main menu.
+pub fn get_import_dir() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join("import"))
+}
+
+/// The `.sav` files in the import directory, sorted by name.
+pub fn list_import_files() -> Result<Vec<PathBuf>> {
+    let ext: OsString = OsString::from("sav");
+
+    let dir = match fs::read_dir(get_import_dir()?) {
+        Ok(dir) => dir,
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files = Vec::new();
+
+    for entry in dir {
+        let path = entry?.path();
+
+        if path.extension() == Some(&ext) && path.is_file() {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// Add a save written by the original game, e.g. from another emulator or a
+/// cartridge dump, to the saves as `name`.
+pub fn import_save(source: &Path, name: &str) -> Result<PathBuf> {
+    let path = check_new_name(&get_save_dir()?, name)?;
+    let data = SaveState::import(&fs::read(source)?)?;
+
+    create_save_dir()?;
+    data.write_to_file(&path)?;
+
+    Ok(path)
+}
+
+/// Write the save called `name` to `dest`, in a form that the original game
+/// loads, e.g. on a cartridge.
+pub fn export_save(name: &str, dest: &Path) -> Result<()> {
+    let mut data = SaveState::from_file(&existing_save(&get_save_dir()?, name)?)?;
+    data.update_checksums();
+
+    fs::write(dest, data.as_bytes())
+}
+
+/// Decode the save called `name` into JSON, see [`SaveDocument`].
+pub fn export_save_json(name: &str) -> Result<String> {
+    let data = SaveState::from_file(&existing_save(&get_save_dir()?, name)?)?;
+    let document = data.to_document()?;
+
+    serde_json::to_string_pretty(&document)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Write a [`SaveDocument`] in JSON, as exported by [`export_save_json`], into
+/// the save called `name`. The previous version is kept as a backup.
+pub fn edit_save_json(name: &str, json: &str) -> Result<()> {
+    let path = existing_save(&get_save_dir()?, name)?;
+    let document: SaveDocument = serde_json::from_str(json)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    let mut data = SaveState::from_file(&path)?;
+    data.apply_document(&document)?;
+
+    rotate_backups(&path, settings().save_backups)?;
+    data.write_to_file(&path)?;
+    refresh_metadata(&path, &data)
+}
+
+/// Path of the `n`th most recent backup of `save_path`, where 1 is the most
+/// recent.
+pub fn get_backup_path(save_path: &Path, n: u8) -> PathBuf {
+    let mut path = save_path.to_owned().into_os_string();
+    path.push(format!(".{}", n));
+    PathBuf::from(path)
+}
+
+/// Copy `save_path` to the first backup, shifting the existing backups up by
+/// one and dropping the oldest, so that at most `count` are kept.
+pub fn rotate_backups(save_path: &Path, count: u8) -> Result<()> {
+    if count == 0 || !save_path.exists() {
+        return Ok(());
+    }
+
+    match fs::remove_file(get_backup_path(save_path, count)) {
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        result => result?,
+    }
+
+    for n in (1..count).rev() {
+        let path = get_backup_path(save_path, n);
+
+        if path.exists() {
+            fs::rename(&path, get_backup_path(save_path, n + 1))?;
+        }
+    }
+
+    fs::copy(save_path, get_backup_path(save_path, 1))?;
+
+    Ok(())
+}
+
+/// The backups of `save_path` that can be read and have valid checksums, most
+/// recent first.
+pub fn list_good_backups(save_path: &Path) -> Vec<(PathBuf, SaveState)> {
+    existing_backups(save_path)
+        .into_iter()
+        .filter_map(|path| match SaveState::from_file(&path) {
+            Ok(data) if data.checksums_valid() => Some((path, data)),
+            Ok(_) => {
+                log::warn!("Backup {} has invalid checksums", path.display());
+                None
+            }
+            Err(e) => {
+                log::error!("Error reading backup {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// A snapshot written when the game crashed, holding SRAM with the progress
+/// since the last save copied in, followed by WRAM.
+pub struct EmergencySave {
+    pub path: PathBuf,
+    /// Name of the save file it belongs to.
+    pub name: String,
+}
+
+/// Path of the `n`th most recent autosave of `save_path`, where 1 is the most
+/// recent.
+pub fn get_autosave_path(save_path: &Path, n: u8) -> PathBuf {
+    save_path.with_extension(format!("autosave.{}", n))
+}
+
+/// Write `data` as the most recent autosave of `save_path`, shifting the
+/// existing autosaves up by one and dropping the oldest, so that at most
+/// `slots` are kept. The save itself is left alone.
+pub fn write_autosave(save_path: &Path, data: &SaveState, slots: u8) -> Result<()> {
+    // Left over from when more slots were kept
+    for path in list_autosaves(save_path).iter().skip(slots as usize) {
+        fs::remove_file(path)?;
+    }
+
+    for n in (1..slots).rev() {
+        let path = get_autosave_path(save_path, n);
+
+        if path.exists() {
+            fs::rename(&path, get_autosave_path(save_path, n + 1))?;
+        }
+    }
+
+    data.write_to_file(&get_autosave_path(save_path, 1))
+}
+
+/// The autosaves of `save_path`, most recent first.
+pub fn list_autosaves(save_path: &Path) -> Vec<PathBuf> {
+    (1..=u8::MAX)
+        .map(|n| get_autosave_path(save_path, n))
+        .take_while(|path| path.exists())
+        .collect()
+}
+
+const EMERGENCY_EXT: &str = "emergency";
+
+/// Where to write an emergency snapshot of `save_path`, next to it.
+pub fn get_emergency_save_path(save_path: &Path, timestamp: u64) -> PathBuf {
+    let stem = save_path.file_stem().unwrap_or_default().to_string_lossy();
+
+    save_path
+        .with_file_name(format!("{}-{}", stem, timestamp))
+        .with_extension(EMERGENCY_EXT)
+}
+
+pub fn list_emergency_saves() -> Result<Vec<EmergencySave>> {
+    let ext = OsString::from(EMERGENCY_EXT);
+
+    let dir = match fs::read_dir(get_save_dir()?) {
+        Ok(dir) => dir,
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files = Vec::new();
+
+    for entry in dir {
+        let path = entry?.path();
+
+        if path.extension() != Some(&ext) || !path.is_file() {
+            continue;
+        }
+
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.rsplit_once('-'))
+            .map(|(name, _timestamp)| name.to_owned());
+
+        if let Some(name) = name {
+            files.push(EmergencySave { path, name });
+        }
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(files)
+}
+
+/// Replace the save file with the SRAM in an emergency snapshot. The save file
+/// is kept as the most recent backup.
+pub fn restore_emergency_save(emergency: &EmergencySave) -> Result<PathBuf> {
+    let save_path = get_save_path(&emergency.name)?;
+    let data = SaveState::from_file(&emergency.path)?;
+
+    rotate_backups(&save_path, settings().save_backups.max(1))?;
+    data.write_to_file(&save_path)?;
+    fs::remove_file(&emergency.path)?;
+
+    Ok(save_path)
+}
+
+/// Move an emergency snapshot out of the save directory, so that restoring it
+/// isn't offered again.
+pub fn dismiss_emergency_save(emergency: &EmergencySave) -> Result<()> {
+    let dir = crate::crash::get_crash_dir()?;
+    fs::create_dir_all(&dir)?;
+    fs::rename(
+        &emergency.path,
+        dir.join(emergency.path.file_name().unwrap()),
+    )
+}
+
+/// Give the save called `old` and its backups the name `new`.
+pub fn rename_save(old: &str, new: &str) -> Result<()> {
+    rename_save_in(&get_save_dir()?, old, new)
+}
+
+/// Copy the save called `name` to a new save called `new`, e.g. to branch off
+/// a playthrough. Backups aren't copied.
+pub fn duplicate_save(name: &str, new: &str) -> Result<()> {
+    duplicate_save_in(&get_save_dir()?, name, new)
+}
+
+/// Remove the save called `name` and its backups.
+pub fn delete_save(name: &str) -> Result<()> {
+    delete_save_in(&get_save_dir()?, name)
+}
+
+/// Check that a save called `name` can be created in `dir`, without
+/// replacing an existing one.
+fn check_new_name(dir: &Path, name: &str) -> Result<PathBuf> {
+    if !is_valid_save_name(name) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{:?} can't be used as a save name, it must be 1 to {} characters without . / \\ or :",
+                name, MAX_SAVE_NAME_LEN
+            ),
+        ));
+    }
+
+    let path = save_path_in(dir, name);
+
+    if path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("A save called {} already exists", name),
+        ));
+    }
+
+    // Backups, autosaves or sidecars left behind would otherwise be replaced
+    if let Some(file) = existing_files(&path).first() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} is in the way of a save called {}", file.display(), name),
+        ));
+    }
+
+    Ok(path)
+}
+
+/// Check that there is a save called `name` in `dir`.
+fn existing_save(dir: &Path, name: &str) -> Result<PathBuf> {
+    let path = save_path_in(dir, name);
+
+    if !path.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("There is no save called {}", name),
+        ));
+    }
+
+    Ok(path)
+}
+
+/// The backups of `save_path` that exist, most recent first.
+fn existing_backups(save_path: &Path) -> Vec<PathBuf> {
+    (1..=u8::MAX)
+        .map(|n| get_backup_path(save_path, n))
+        .take_while(|path| path.exists())
+        .collect()
+}
+
+fn rename_save_in(dir: &Path, old: &str, new: &str) -> Result<()> {
+    let old_path = existing_save(dir, old)?;
+    let new_path = check_new_name(dir, new)?;
+
+    for (n, backup) in (1..).zip(existing_backups(&old_path)) {
+        fs::rename(backup, get_backup_path(&new_path, n))?;
+    }
+
+    for (n, autosave) in (1..).zip(list_autosaves(&old_path)) {
+        fs::rename(autosave, get_autosave_path(&new_path, n))?;
+    }
+
+    for (from, to) in sidecar_paths(&old_path).zip(sidecar_paths(&new_path)) {
+        if from.exists() {
+            fs::rename(from, to)?;
+        }
+    }
+
+    fs::rename(old_path, new_path)
+}
+
+fn duplicate_save_in(dir: &Path, name: &str, new: &str) -> Result<()> {
+    let path = existing_save(dir, name)?;
+    let new_path = check_new_name(dir, new)?;
+
+    for (from, to) in sidecar_paths(&path).zip(sidecar_paths(&new_path)) {
+        if from.exists() {
+            fs::copy(from, to)?;
+        }
+    }
+
+    SaveState::from_file(&path)?.write_to_file(&new_path)
+}
+
+fn delete_save_in(dir: &Path, name: &str) -> Result<()> {
+    let path = existing_save(dir, name)?;
+
+    for file in existing_backups(&path)
+        .into_iter()
+        .chain(list_autosaves(&path))
+    {
+        fs::remove_file(file)?;
+    }
+
+    for sidecar in sidecar_paths(&path) {
+        if sidecar.exists() {
+            fs::remove_file(sidecar)?;
+        }
+    }
+
+    fs::remove_file(path)
+}
+
+/// Every file that exists and goes with the save at `save_path`, apart from
+/// the save itself.
+fn existing_files(save_path: &Path) -> Vec<PathBuf> {
+    existing_backups(save_path)
+        .into_iter()
+        .chain(list_autosaves(save_path))
+        .chain(sidecar_paths(save_path).filter(|path| path.exists()))
+        .collect()
+}
+
+/// The metadata and thumbnail that go with a save.
+fn sidecar_paths(save_path: &Path) -> impl Iterator<Item = PathBuf> {
+    [get_metadata_path(save_path), get_thumbnail_path(save_path)].into_iter()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_save(dir: &Path, name: &str, marker: u8) {
+        let mut data = SaveState::new();
+        data.set_byte(0, marker);
+        data.write_to_file(&save_path_in(dir, name)).unwrap();
+    }
+
+    fn read_marker(path: &Path) -> u8 {
+        SaveState::from_file(&path.to_owned()).unwrap().byte(0)
+    }
+
+    #[test]
+    fn test_rename_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        write_save(dir, "RED", 1);
+        rotate_backups(&save_path_in(dir, "RED"), 3).unwrap();
+        write_save(dir, "RED", 2);
+        fs::write(get_metadata_path(&save_path_in(dir, "RED")), "").unwrap();
+
+        rename_save_in(dir, "RED", "BLUE").unwrap();
+
+        assert!(!save_path_in(dir, "RED").exists());
+        assert!(!get_metadata_path(&save_path_in(dir, "RED")).exists());
+        assert!(get_metadata_path(&save_path_in(dir, "BLUE")).exists());
+        assert!(!get_backup_path(&save_path_in(dir, "RED"), 1).exists());
+        assert_eq!(read_marker(&save_path_in(dir, "BLUE")), 2);
+        assert_eq!(
+            read_marker(&get_backup_path(&save_path_in(dir, "BLUE"), 1)),
+            1
+        );
+    }
+
+    #[test]
+    fn test_duplicate_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        write_save(dir, "RED", 1);
+        duplicate_save_in(dir, "RED", "RED 2").unwrap();
+
+        assert_eq!(read_marker(&save_path_in(dir, "RED")), 1);
+        assert_eq!(read_marker(&save_path_in(dir, "RED 2")), 1);
+    }
+
+    #[test]
+    fn test_delete_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        write_save(dir, "RED", 1);
+        rotate_backups(&save_path_in(dir, "RED"), 3).unwrap();
+        write_save(dir, "REDDER", 2);
+
+        delete_save_in(dir, "RED").unwrap();
+
+        assert!(!save_path_in(dir, "RED").exists());
+        assert!(!get_backup_path(&save_path_in(dir, "RED"), 1).exists());
+        assert!(save_path_in(dir, "REDDER").exists());
+        assert!(delete_save_in(dir, "RED").is_err());
+    }
+
+    #[test]
+    fn test_write_autosave() {
+        let dir = tempfile::tempdir().unwrap();
+        let save_path = save_path_in(dir.path(), "RED");
+        write_save(dir.path(), "RED", 1);
+
+        for marker in 2..=4 {
+            let mut data = SaveState::new();
+            data.set_byte(0, marker);
+            write_autosave(&save_path, &data, 2).unwrap();
+        }
+
+        let autosaves = list_autosaves(&save_path);
+        assert_eq!(autosaves.len(), 2);
+        assert_eq!(read_marker(&autosaves[0]), 4);
+        assert_eq!(read_marker(&autosaves[1]), 3);
+        assert_eq!(read_marker(&save_path), 1);
+
+        // Fewer slots than before
+        write_autosave(&save_path, &SaveState::new(), 1).unwrap();
+        assert_eq!(list_autosaves(&save_path).len(), 1);
+    }
+
+    #[test]
+    fn test_refuses_to_clobber() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        write_save(dir, "RED", 1);
+        write_save(dir, "BLUE", 2);
+
+        let error = rename_save_in(dir, "RED", "BLUE").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+
+        let error = duplicate_save_in(dir, "RED", "BLUE").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+
+        assert!(rename_save_in(dir, "RED", "../RED").is_err());
+        assert!(rename_save_in(dir, "RED", "BLUE.v2").is_err());
+
+        // Files left over from an earlier GREEN
+        fs::write(get_thumbnail_path(&save_path_in(dir, "GREEN")), "").unwrap();
+        let error = duplicate_save_in(dir, "RED", "GREEN").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        write_autosave(&save_path_in(dir, "YELLOW"), &SaveState::new(), 1).unwrap();
+        let error = rename_save_in(dir, "RED", "YELLOW").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(rename_save_in(dir, "GREEN", "YELLOW").is_err());
+
+        assert_eq!(read_marker(&save_path_in(dir, "RED")), 1);
+        assert_eq!(read_marker(&save_path_in(dir, "BLUE")), 2);
+    }
+}
@@ -0,0 +1,48 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::saves;
+
+fn get_screenshot_dir() -> io::Result<PathBuf> {
+    Ok(saves::get_data_dir()?.join("screenshots"))
+}
+
+/// Save a `width` × `height` frame, as sent by the game to the frontend, as a
+/// PNG file in the screenshots directory.
+pub fn save_screenshot(frame: &[u8], width: usize, height: usize) -> io::Result<PathBuf> {
+    let dir = get_screenshot_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut path = dir.join(format!("screenshot-{}.png", timestamp));
+    let mut suffix = 1;
+
+    while path.exists() {
+        suffix += 1;
+        path = dir.join(format!("screenshot-{}-{}.png", timestamp, suffix));
+    }
+
+    save_png(&path, frame, width, height)?;
+
+    Ok(path)
+}
+
+/// Write an RGB frame to `path` as a PNG file.
+pub(crate) fn save_png(path: &Path, frame: &[u8], width: usize, height: usize) -> io::Result<()> {
+    image::save_buffer_with_format(
+        path,
+        frame,
+        width as u32,
+        height as u32,
+        image::ColorType::Rgb8,
+        image::ImageFormat::Png,
+    )
+    .map_err(io::Error::other)
+}
@@ -0,0 +1,644 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bindings::KeyBindings,
+    config,
+    game::constants::misc_constants::{
+        BIT_BATTLE_ANIMATION, BIT_BATTLE_SHIFT, TEXT_DELAY_FAST, TEXT_DELAY_MEDIUM, TEXT_DELAY_SLOW,
+    },
+    palette::PaletteChoice,
+    save_state::PokemonSpecies,
+};
+
+const SETTINGS_FILE: &str = "settings.toml";
+const LEGACY_BINDINGS_FILE: &str = "bindings.toml";
+
+const MAX_SAVE_BACKUPS: u8 = 20;
+const MAX_AUTOSAVE_SLOTS: u8 = 9;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextSpeed {
+    Fast,
+    Medium,
+    Slow,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BattleStyle {
+    Shift,
+    Set,
+}
+
+/// What to do when the window loses focus.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusLost {
+    Pause,
+    Mute,
+    KeepRunning,
+}
+
+/// The options from the in-game OPTION menu, used when starting the game.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameOptions {
+    pub text_speed: TextSpeed,
+    pub battle_animation: bool,
+    pub battle_style: BattleStyle,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        GameOptions {
+            text_speed: TextSpeed::Medium,
+            battle_animation: true,
+            battle_style: BattleStyle::Shift,
+        }
+    }
+}
+
+impl GameOptions {
+    /// The value of `wOptions` for these options.
+    pub fn to_byte(&self) -> u8 {
+        let text_speed = match self.text_speed {
+            TextSpeed::Fast => TEXT_DELAY_FAST,
+            TextSpeed::Medium => TEXT_DELAY_MEDIUM,
+            TextSpeed::Slow => TEXT_DELAY_SLOW,
+        };
+
+        let battle_animation = if self.battle_animation {
+            0
+        } else {
+            1 << BIT_BATTLE_ANIMATION
+        };
+
+        let battle_style = match self.battle_style {
+            BattleStyle::Shift => 0,
+            BattleStyle::Set => 1 << BIT_BATTLE_SHIFT,
+        };
+
+        text_speed | battle_animation | battle_style
+    }
+}
+
+/// When to write autosaves, which are kept next to the save they belong to
+/// without ever replacing it.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutosaveSettings {
+    pub enabled: bool,
+    /// Minutes of play time between autosaves, or 0 to only autosave on the
+    /// other triggers.
+    pub interval_minutes: u16,
+    /// Autosave after walking into a Pokémon Center.
+    pub on_pokemon_center: bool,
+    /// Autosave after a battle in which a Pokémon was caught.
+    pub on_catch: bool,
+    /// How many autosaves to keep for each save, as `name.autosave.1`,
+    /// `name.autosave.2` and so on.
+    pub slots: u8,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        AutosaveSettings {
+            enabled: false,
+            interval_minutes: 15,
+            on_pokemon_center: true,
+            on_catch: true,
+            slots: 3,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Emulation speed preset, where 1 is the original speed.
+    pub speed: u8,
+    /// How many times the original speed to run while the fast forward key
+    /// is held, or 0 to run as fast as possible.
+    pub fast_forward_speed: u8,
+    /// Wait for the display's refresh before showing a frame.
+    pub vsync: bool,
+    pub on_focus_lost: FocusLost,
+    /// Resolution to render at, and the initial window size, as a multiple of
+    /// the Game Boy screen.
+    pub scale: u8,
+    pub filter: Filter,
+    pub palette: PaletteChoice,
+    /// Show the FPS and emulation speed counters.
+    pub show_fps: bool,
+    /// Music volume, from 0 to 100.
+    pub music_volume: u8,
+    /// Sound effect volume, from 0 to 100.
+    pub sfx_volume: u8,
+    /// Where save files are kept, instead of the default data directory.
+    pub save_dir: Option<PathBuf>,
+    /// How many older copies of each save to keep, as `name.sav.1`,
+    /// `name.sav.2`, and so on.
+    pub save_backups: u8,
+    pub autosave: AutosaveSettings,
+    /// Where the music files are read from, instead of the bundled resources.
+    pub music_dir: Option<PathBuf>,
+    /// Directory with replacement UI atlases and an `atlases.toml` manifest.
+    pub asset_pack: Option<PathBuf>,
+    #[serde(with = "species_name")]
+    pub starter: PokemonSpecies,
+    pub game_options: GameOptions,
+    pub key_bindings: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            speed: 1,
+            fast_forward_speed: 4,
+            vsync: true,
+            on_focus_lost: FocusLost::Pause,
+            scale: 4,
+            filter: Filter::Nearest,
+            palette: PaletteChoice::default(),
+            show_fps: false,
+            music_volume: 100,
+            sfx_volume: 100,
+            save_dir: None,
+            save_backups: 3,
+            autosave: AutosaveSettings::default(),
+            music_dir: None,
+            asset_pack: None,
+            starter: PokemonSpecies::Pikachu,
+            game_options: GameOptions::default(),
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Load settings from `path`, falling back to the defaults for anything
+    /// that isn't mentioned in the file.
+    pub fn load(path: &Path) -> io::Result<Settings> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Settings::default()),
+            Err(e) => return Err(e),
+        };
+
+        let mut result: Settings =
+            toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        result.speed = result.speed.clamp(1, 6);
+        result.fast_forward_speed = result.fast_forward_speed.min(16);
+        result.scale = result.scale.clamp(1, 8);
+        result.music_volume = result.music_volume.min(100);
+        result.sfx_volume = result.sfx_volume.min(100);
+        result.save_backups = result.save_backups.min(MAX_SAVE_BACKUPS);
+        result.autosave.slots = result.autosave.slots.clamp(1, MAX_AUTOSAVE_SLOTS);
+        result.key_bindings.fill_missing_defaults();
+
+        Ok(result)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text =
+            toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        fs::write(path, text)
+    }
+}
+
+/// Settings given on the command line or in the environment. These take
+/// precedence over the config file, but only for the current session.
+#[derive(Debug, Default, Clone, clap::Args)]
+pub struct Overrides {
+    /// Which Pokemon to start with
+    #[arg(long, env = "RUSTIC_YELLOW_STARTER")]
+    pub starter: Option<PokemonSpecies>,
+
+    /// Emulation speed preset, where 1 is the original speed
+    #[arg(long, env = "RUSTIC_YELLOW_SPEED", value_parser = clap::value_parser!(u8).range(1..=6))]
+    pub speed: Option<u8>,
+
+    /// Resolution to render at, as a multiple of the Game Boy screen
+    #[arg(long, env = "RUSTIC_YELLOW_SCALE", value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub scale: Option<u8>,
+
+    /// How the screen is filtered when scaled
+    #[arg(long, env = "RUSTIC_YELLOW_FILTER")]
+    pub filter: Option<Filter>,
+
+    /// Palette for the screen: gbc, gbc_raw, dmg_green, pocket_grey,
+    /// protanopia, deuteranopia, tritanopia, or the name of a custom palette
+    #[arg(long, env = "RUSTIC_YELLOW_PALETTE")]
+    pub palette: Option<PaletteChoice>,
+
+    /// What to do when the window loses focus
+    #[arg(long, env = "RUSTIC_YELLOW_ON_FOCUS_LOST")]
+    pub on_focus_lost: Option<FocusLost>,
+
+    /// Music volume, from 0 to 100
+    #[arg(long, env = "RUSTIC_YELLOW_MUSIC_VOLUME", value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub music_volume: Option<u8>,
+
+    /// Sound effect volume, from 0 to 100
+    #[arg(long, env = "RUSTIC_YELLOW_SFX_VOLUME", value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub sfx_volume: Option<u8>,
+
+    /// Directory to keep save files in
+    #[arg(long, env = "RUSTIC_YELLOW_SAVE_DIR")]
+    pub save_dir: Option<PathBuf>,
+
+    /// Keep settings, saves and everything else next to the executable
+    #[arg(long, env = "RUSTIC_YELLOW_PORTABLE")]
+    pub portable: bool,
+
+    /// Directory to read music files from
+    #[arg(long, env = "RUSTIC_YELLOW_MUSIC_DIR")]
+    pub music_dir: Option<PathBuf>,
+
+    /// Directory to load replacement UI atlases from
+    #[arg(long, env = "RUSTIC_YELLOW_ASSET_PACK")]
+    pub asset_pack: Option<PathBuf>,
+}
+
+impl Overrides {
+    fn apply(&self, settings: &mut Settings) {
+        if let Some(starter) = self.starter {
+            settings.starter = starter;
+        }
+        if let Some(speed) = self.speed {
+            settings.speed = speed;
+        }
+        if let Some(scale) = self.scale {
+            settings.scale = scale;
+        }
+        if let Some(filter) = self.filter {
+            settings.filter = filter;
+        }
+        if let Some(palette) = &self.palette {
+            settings.palette = palette.clone();
+        }
+        if let Some(on_focus_lost) = self.on_focus_lost {
+            settings.on_focus_lost = on_focus_lost;
+        }
+        if let Some(music_volume) = self.music_volume {
+            settings.music_volume = music_volume;
+        }
+        if let Some(sfx_volume) = self.sfx_volume {
+            settings.sfx_volume = sfx_volume;
+        }
+        if let Some(save_dir) = &self.save_dir {
+            settings.save_dir = Some(save_dir.clone());
+        }
+        if let Some(music_dir) = &self.music_dir {
+            settings.music_dir = Some(music_dir.clone());
+        }
+        if let Some(asset_pack) = &self.asset_pack {
+            settings.asset_pack = Some(asset_pack.clone());
+        }
+    }
+
+    /// Put back the values from `saved` for everything that was overridden,
+    /// so that overrides don't end up in the config file.
+    fn restore(&self, settings: &mut Settings, saved: &Settings) {
+        if self.starter.is_some() {
+            settings.starter = saved.starter;
+        }
+        if self.speed.is_some() {
+            settings.speed = saved.speed;
+        }
+        if self.scale.is_some() {
+            settings.scale = saved.scale;
+        }
+        if self.filter.is_some() {
+            settings.filter = saved.filter;
+        }
+        if self.palette.is_some() {
+            settings.palette = saved.palette.clone();
+        }
+        if self.on_focus_lost.is_some() {
+            settings.on_focus_lost = saved.on_focus_lost;
+        }
+        if self.music_volume.is_some() {
+            settings.music_volume = saved.music_volume;
+        }
+        if self.sfx_volume.is_some() {
+            settings.sfx_volume = saved.sfx_volume;
+        }
+        if self.save_dir.is_some() {
+            settings.save_dir = saved.save_dir.clone();
+        }
+        if self.music_dir.is_some() {
+            settings.music_dir = saved.music_dir.clone();
+        }
+        if self.asset_pack.is_some() {
+            settings.asset_pack = saved.asset_pack.clone();
+        }
+    }
+}
+
+pub(crate) mod species_name {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::save_state::PokemonSpecies;
+
+    pub fn serialize<S: Serializer>(species: &PokemonSpecies, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&format!("{:?}", species))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<PokemonSpecies, D::Error> {
+        String::deserialize(d)?.parse().map_err(D::Error::custom)
+    }
+
+    pub mod option {
+        use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+        use crate::save_state::PokemonSpecies;
+
+        pub fn serialize<S: Serializer>(
+            species: &Option<PokemonSpecies>,
+            s: S,
+        ) -> Result<S::Ok, S::Error> {
+            match species {
+                Some(species) => super::serialize(species, s),
+                None => s.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            d: D,
+        ) -> Result<Option<PokemonSpecies>, D::Error> {
+            Option::<String>::deserialize(d)?
+                .map(|name| name.parse().map_err(D::Error::custom))
+                .transpose()
+        }
+    }
+}
+
+static SETTINGS: OnceLock<RwLock<Settings>> = OnceLock::new();
+static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+
+fn read_settings_file() -> Settings {
+    let path = match config::get_config_path(SETTINGS_FILE) {
+        Ok(path) => path,
+        Err(e) => {
+            log::error!("Error finding the settings file: {}", e);
+            return Settings::default();
+        }
+    };
+
+    match config::get_config_path(LEGACY_BINDINGS_FILE) {
+        Ok(legacy_path) => read_settings(&path, Some(&legacy_path)),
+        Err(_) => read_settings(&path, None),
+    }
+}
+
+/// Read the settings from `path`, taking the key bindings from the legacy
+/// bindings file at `legacy_path` if the settings file doesn't exist yet.
+fn read_settings(path: &Path, legacy_path: Option<&Path>) -> Settings {
+    let mut settings = Settings::load(path).unwrap_or_else(|e| {
+        log::error!("Error reading settings from {}: {}", path.display(), e);
+        Settings::default()
+    });
+
+    if !path.exists() {
+        if let Some(bindings) = legacy_path.and_then(read_legacy_bindings) {
+            settings.key_bindings = bindings;
+        }
+    }
+
+    settings
+}
+
+/// The key bindings from before they were part of the settings file, so that
+/// they carry over until the settings are first written.
+fn read_legacy_bindings(path: &Path) -> Option<KeyBindings> {
+    let text = fs::read_to_string(path).ok()?;
+
+    match toml::from_str::<KeyBindings>(&text) {
+        Ok(mut bindings) => {
+            bindings.fill_missing_defaults();
+            log::info!("Using the key bindings from {}", path.display());
+            Some(bindings)
+        }
+        Err(e) => {
+            log::error!("Error reading key bindings from {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Load the settings from the config file and apply `overrides` on top. This
+/// should be called once, before the game is started.
+pub fn init_settings(overrides: Overrides) {
+    config::set_portable(overrides.portable);
+
+    let mut settings = read_settings_file();
+    overrides.apply(&mut settings);
+
+    if SETTINGS.set(RwLock::new(settings)).is_err() {
+        panic!("Settings have already been initialized");
+    }
+
+    let _ = OVERRIDES.set(overrides);
+}
+
+fn lock() -> &'static RwLock<Settings> {
+    SETTINGS.get_or_init(|| RwLock::new(Settings::default()))
+}
+
+/// The current settings. Don't hold on to the guard for longer than needed,
+/// since changing the settings has to wait for it.
+pub fn settings() -> RwLockReadGuard<'static, Settings> {
+    lock().read().unwrap()
+}
+
+pub fn settings_mut() -> RwLockWriteGuard<'static, Settings> {
+    lock().write().unwrap()
+}
+
+/// Write the current settings to the config file, leaving out anything that
+/// was overridden for this session.
+pub fn write_settings() -> io::Result<()> {
+    let mut settings = settings().clone();
+
+    if let Some(overrides) = OVERRIDES.get() {
+        overrides.restore(&mut settings, &read_settings_file());
+    }
+
+    config::create_config_dir()?;
+    settings.save(&config::get_config_path(SETTINGS_FILE)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bindings::Action, keypad::KeyboardKey};
+
+    #[test]
+    fn test_settings_toml() {
+        let mut settings = Settings {
+            speed: 3,
+            fast_forward_speed: 0,
+            on_focus_lost: FocusLost::Mute,
+            filter: Filter::Linear,
+            palette: PaletteChoice::Custom("mine".to_string()),
+            music_volume: 40,
+            save_dir: Some(PathBuf::from("saves")),
+            starter: PokemonSpecies::Bulbasaur,
+            game_options: GameOptions {
+                text_speed: TextSpeed::Slow,
+                battle_animation: false,
+                battle_style: BattleStyle::Set,
+            },
+            ..Settings::default()
+        };
+        settings.autosave.enabled = true;
+        settings
+            .key_bindings
+            .rebind(Action::Pause, KeyboardKey::Escape);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        settings.save(&path).unwrap();
+
+        assert_eq!(Settings::load(&path).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+
+        fs::write(&path, "scale = 2\n").unwrap();
+        assert_eq!(
+            Settings::load(&path).unwrap(),
+            Settings {
+                scale: 2,
+                ..Settings::default()
+            }
+        );
+
+        fs::write(&path, "scale = \"big\"\n").unwrap();
+        let err = Settings::load(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_load_clamped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+
+        fs::write(
+            &path,
+            "speed = 0\n\
+             fast_forward_speed = 99\n\
+             scale = 20\n\
+             music_volume = 200\n\
+             sfx_volume = 101\n\
+             save_backups = 255\n\
+             [autosave]\n\
+             slots = 0\n",
+        )
+        .unwrap();
+        let settings = Settings::load(&path).unwrap();
+        assert_eq!(settings.speed, 1);
+        assert_eq!(settings.fast_forward_speed, 16);
+        assert_eq!(settings.scale, 8);
+        assert_eq!(settings.music_volume, 100);
+        assert_eq!(settings.sfx_volume, 100);
+        assert_eq!(settings.save_backups, MAX_SAVE_BACKUPS);
+        assert_eq!(settings.autosave.slots, 1);
+
+        fs::write(&path, "speed = 9\n[autosave]\nslots = 50\n").unwrap();
+        let settings = Settings::load(&path).unwrap();
+        assert_eq!(settings.speed, 6);
+        assert_eq!(settings.autosave.slots, MAX_AUTOSAVE_SLOTS);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let saved = Settings {
+            speed: 2,
+            music_volume: 50,
+            music_dir: Some(PathBuf::from("music")),
+            ..Settings::default()
+        };
+        let overrides = Overrides {
+            speed: Some(5),
+            palette: Some(PaletteChoice::DmgGreen),
+            save_dir: Some(PathBuf::from("saves")),
+            ..Overrides::default()
+        };
+
+        let mut settings = saved.clone();
+        overrides.apply(&mut settings);
+        assert_eq!(
+            settings,
+            Settings {
+                speed: 5,
+                palette: PaletteChoice::DmgGreen,
+                save_dir: Some(PathBuf::from("saves")),
+                ..saved.clone()
+            }
+        );
+
+        overrides.restore(&mut settings, &saved);
+        assert_eq!(settings, saved);
+
+        let mut settings = saved.clone();
+        Overrides::default().apply(&mut settings);
+        assert_eq!(settings, saved);
+    }
+
+    #[test]
+    fn test_legacy_bindings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        let legacy_path = dir.path().join(LEGACY_BINDINGS_FILE);
+
+        assert_eq!(read_legacy_bindings(&legacy_path), None);
+        assert_eq!(
+            read_settings(&path, Some(&legacy_path)),
+            Settings::default()
+        );
+
+        fs::write(&legacy_path, "save_state = [\"F6\"]\npause = [\"Space\"]\n").unwrap();
+        let settings = read_settings(&path, Some(&legacy_path));
+        let bindings = &settings.key_bindings;
+        assert_eq!(bindings.keys_for(Action::QuickSave), &[KeyboardKey::F6]);
+        assert_eq!(bindings.keys_for(Action::Pause), &[KeyboardKey::Space]);
+        assert_eq!(bindings.keys_for(Action::Select), &[]);
+        assert_eq!(bindings.keys_for(Action::Start), &[KeyboardKey::Return]);
+
+        // Once the settings file exists, the legacy bindings are ignored.
+        fs::write(&path, "scale = 2\n").unwrap();
+        let settings = read_settings(&path, Some(&legacy_path));
+        assert_eq!(settings.key_bindings, KeyBindings::default());
+
+        fs::remove_file(&path).unwrap();
+        fs::write(&legacy_path, "pause = \"Space\"\n").unwrap();
+        assert_eq!(read_legacy_bindings(&legacy_path), None);
+        assert_eq!(
+            read_settings(&path, Some(&legacy_path)),
+            Settings::default()
+        );
+    }
+}
@@ -1,4 +1,23 @@
-use rodio::{OutputStream, OutputStreamHandle, Sink};
+use std::time::Duration;
+
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+
+use crate::{pacing, settings::settings};
+
+/// How often playing sounds check if they should be paused.
+const PAUSE_POLL: Duration = Duration::from_millis(10);
+
+/// Make `source` hold its position while the game is paused, which can be
+/// changed from any thread.
+fn pausable<TSource>(source: TSource) -> impl Source<Item = TSource::Item> + Send + 'static
+where
+    TSource: Source + Send + 'static,
+    TSource::Item: rodio::Sample + Send,
+{
+    source
+        .pausable(pacing::is_paused())
+        .periodic_access(PAUSE_POLL, |source| source.set_paused(pacing::is_paused()))
+}
 
 pub trait Sfx<TSource> {
     fn open(self) -> TSource;
@@ -12,6 +31,9 @@ pub struct Sound2 {
     handle: OutputStreamHandle,
     music: Option<(u32, Sink)>,
     sfx: Option<Sink>,
+    muted: bool,
+    /// Music and sound effect volume the sinks were last set to.
+    volume: (f32, f32),
     _stream: OutputStream,
 }
 
@@ -24,6 +46,52 @@ impl Sound2 {
             music: None,
             handle,
             sfx: None,
+            muted: false,
+            volume: (1.0, 1.0),
+        }
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.update_volume();
+    }
+
+    /// Apply the volume settings to what's playing, if they have changed.
+    pub fn update_volume(&mut self) {
+        let volume = (self.music_volume(), self.sfx_volume());
+
+        if volume == self.volume {
+            return;
+        }
+
+        self.volume = volume;
+
+        if let Some((_, sink)) = self.music.as_ref() {
+            sink.set_volume(volume.0);
+        }
+
+        if let Some(sink) = self.sfx.as_ref() {
+            sink.set_volume(volume.1);
+        }
+    }
+
+    fn music_volume(&self) -> f32 {
+        if self.muted || pacing::is_muted_by_focus() {
+            0.0
+        } else {
+            settings().music_volume as f32 / 100.0
+        }
+    }
+
+    fn sfx_volume(&self) -> f32 {
+        if self.muted || pacing::is_muted_by_focus() {
+            0.0
+        } else {
+            settings().sfx_volume as f32 / 100.0
         }
     }
 
@@ -57,7 +125,8 @@ impl Sound2 {
         self.stop_music();
 
         let sink = Sink::try_new(&self.handle).unwrap();
-        sink.append(music.open());
+        sink.set_volume(self.music_volume());
+        sink.append(pausable(music.open()));
         self.music = Some((id, sink));
     }
 
@@ -73,7 +142,8 @@ impl Sound2 {
         }
 
         let sink = Sink::try_new(&self.handle).unwrap();
-        sink.append(sound.open());
+        sink.set_volume(self.sfx_volume());
+        sink.append(pausable(sound.open()));
         self.sfx = Some(sink);
     }
 }